@@ -0,0 +1,38 @@
+use automata::dfa::DFA;
+
+// Words over {a, b} ending in `ab`, with two redundant copies of the start.
+const CODE: &str = "
+states = [q0, q1, q2, q3, q4]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q2]
+transitions =
+    q0,a = q1;
+    q0,b = q3;
+    q1,a = q1;
+    q1,b = q2;
+    q2,a = q1;
+    q2,b = q4;
+    q3,a = q1;
+    q3,b = q0;
+    q4,a = q1;
+    q4,b = q3;
+";
+
+fn main() {
+    let dfa: DFA = CODE.parse().expect("Error parsing dfa");
+    println!("{} states before minimizing", dfa.states.len());
+
+    let minimal = dfa.minimize();
+    println!("{} states after minimizing:\n", minimal.states.len());
+    println!("{minimal}\n");
+
+    for word in [&["a", "b"][..], &["b", "a"], &["b", "b", "a", "b"], &[]] {
+        let verdict = if minimal.accepts(word) {
+            "accepted"
+        } else {
+            "rejected"
+        };
+        println!("{word:?} is {verdict}");
+    }
+}
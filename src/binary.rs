@@ -0,0 +1,197 @@
+use crate::dfa::DFA;
+use std::collections::{BTreeMap, HashMap};
+
+// Leads every encoding, so other data is rejected before anything is read.
+const MAGIC: &[u8; 4] = b"DFA1";
+
+impl DFA {
+    /// Encodes the DFA in a compact binary format that `from_bytes` reads
+    /// back much faster than the text format parses. Numbers are
+    /// little-endian `u32`s, strings are length-prefixed UTF-8, and states
+    /// and symbols are referred to by their declared index.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let state_index: HashMap<&str, u32> = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (state.as_str(), i as u32))
+            .collect();
+        // Index plus one, with 0 for a missing state.
+        let optional = |state: Option<&str>| state.map_or(0, |state| state_index[state] + 1);
+
+        let mut bytes = MAGIC.to_vec();
+        write_u32(&mut bytes, self.name.is_some() as u32);
+        if let Some(name) = &self.name {
+            write_str(&mut bytes, name);
+        }
+        write_u32(&mut bytes, self.states.len() as u32);
+        for state in &self.states {
+            write_str(&mut bytes, state);
+        }
+        write_u32(&mut bytes, self.alphabet.len() as u32);
+        for symbol in &self.alphabet {
+            write_str(&mut bytes, symbol);
+        }
+        for state in &self.states {
+            for symbol in &self.alphabet {
+                write_u32(&mut bytes, optional(self.step(state, symbol)));
+            }
+        }
+        write_u32(&mut bytes, state_index[self.starting_state.as_str()]);
+        write_u32(&mut bytes, self.accepting_states.len() as u32);
+        for state in &self.accepting_states {
+            write_u32(&mut bytes, state_index[state.as_str()]);
+        }
+        write_u32(&mut bytes, optional(self.dead_state.as_deref()));
+        bytes
+    }
+
+    /// Decodes the output of `to_bytes`. Truncated or corrupt data is an
+    /// error rather than a panic.
+    pub fn from_bytes(bytes: &[u8]) -> Result<DFA, String> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("Not a binary DFA.".to_string());
+        }
+        let name = match reader.u32()? {
+            0 => None,
+            _ => Some(reader.string()?),
+        };
+        let states = reader.strings()?;
+        let alphabet = reader.strings()?;
+        if states.is_empty() {
+            return Err("States cannot be empty.".to_string());
+        }
+        if alphabet.is_empty() {
+            return Err("Alphabet cannot be empty.".to_string());
+        }
+
+        let mut transition = BTreeMap::new();
+        for state in &states {
+            for symbol in &alphabet {
+                if let Some(end) = reader.optional_state(&states)? {
+                    transition.insert((state.clone(), symbol.clone()), end);
+                }
+            }
+        }
+        let starting_state = reader.state(&states)?;
+        let accepting_states = (0..reader.u32()?)
+            .map(|_| reader.state(&states))
+            .collect::<Result<_, _>>()?;
+        let dead_state = reader.optional_state(&states)?;
+        if reader.position != bytes.len() {
+            return Err(format!(
+                "Unexpected data after the DFA at byte {}.",
+                reader.position
+            ));
+        }
+
+        Ok(DFA {
+            name,
+            states,
+            alphabet,
+            transition,
+            starting_state,
+            accepting_states,
+            dead_state,
+            accepting: Default::default(),
+        })
+    }
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_str(bytes: &mut Vec<u8>, value: &str) {
+    write_u32(bytes, value.len() as u32);
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], String> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("Unexpected End of Data.")?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.u32()? as usize;
+        let position = self.position;
+        String::from_utf8(self.take(length)?.to_vec())
+            .map_err(|_| format!("Invalid UTF-8 in string at byte {position}."))
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>, String> {
+        (0..self.u32()?).map(|_| self.string()).collect()
+    }
+
+    fn state(&mut self, states: &[String]) -> Result<String, String> {
+        let index = self.u32()? as usize;
+        states
+            .get(index)
+            .cloned()
+            .ok_or(format!("State index {index} is out of range."))
+    }
+
+    fn optional_state(&mut self, states: &[String]) -> Result<Option<String>, String> {
+        match self.u32()? as usize {
+            0 => Ok(None),
+            index => states
+                .get(index - 1)
+                .cloned()
+                .map(Some)
+                .ok_or(format!("State index {} is out of range.", index - 1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod binary_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn round_trip() {
+        for path in ["./test.dfa", "./three.dfa", "./big.dfa"] {
+            let dfa = DFA::try_from(fs::read_to_string(path).unwrap()).unwrap();
+            assert_eq!(DFA::from_bytes(&dfa.to_bytes()), Ok(dfa), "{path}");
+        }
+        let mut dfa = DFA::random(1000, &["a", "b", "c"], 7);
+        dfa.name = Some("random".to_string());
+        dfa.dead_state = Some(dfa.add_state());
+        assert_eq!(DFA::from_bytes(&dfa.to_bytes()), Ok(dfa));
+    }
+
+    #[test]
+    fn rejects_corrupt_data() {
+        let bytes = DFA::try_from(fs::read_to_string("./test.dfa").unwrap())
+            .unwrap()
+            .to_bytes();
+        assert_eq!(
+            DFA::from_bytes(&bytes[..bytes.len() - 1]),
+            Err("Unexpected End of Data.".to_string())
+        );
+        assert_eq!(
+            DFA::from_bytes(b"states = [q1]"),
+            Err("Not a binary DFA.".to_string())
+        );
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(DFA::from_bytes(&extra).is_err());
+    }
+}
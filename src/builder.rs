@@ -0,0 +1,115 @@
+use crate::dfa::DFA;
+
+/// Builds a `DFA` in code instead of parsing one, starting from
+/// `DFA::default()`. Nothing is checked until `build`, which validates the
+/// result the same way parsing does.
+#[derive(Debug, Clone, Default)]
+pub struct DFABuilder {
+    dfa: DFA,
+}
+
+impl DFA {
+    pub fn builder() -> DFABuilder {
+        DFABuilder::default()
+    }
+}
+
+impl DFABuilder {
+    pub fn name(mut self, name: &str) -> DFABuilder {
+        self.dfa.name = Some(name.to_string());
+        self
+    }
+
+    /// Adds `states` after the ones already declared.
+    pub fn states(mut self, states: &[&str]) -> DFABuilder {
+        self.dfa.states.extend(states.iter().map(|s| s.to_string()));
+        self
+    }
+
+    /// Adds `symbols` after the ones already declared.
+    pub fn alphabet(mut self, symbols: &[&str]) -> DFABuilder {
+        self.dfa
+            .alphabet
+            .extend(symbols.iter().map(|s| s.to_string()));
+        self
+    }
+
+    pub fn starting_state(mut self, state: &str) -> DFABuilder {
+        self.dfa.starting_state = state.to_string();
+        self
+    }
+
+    pub fn accepting(mut self, states: &[&str]) -> DFABuilder {
+        self.dfa
+            .accepting_states
+            .extend(states.iter().map(|s| s.to_string()));
+        self.dfa.accepting_changed();
+        self
+    }
+
+    pub fn dead_state(mut self, state: &str) -> DFABuilder {
+        self.dfa.dead_state = Some(state.to_string());
+        self
+    }
+
+    /// Sets the transition from `start` on `symbol` to `end`, replacing any
+    /// earlier one.
+    pub fn transition(mut self, start: &str, symbol: &str, end: &str) -> DFABuilder {
+        self.dfa
+            .transition
+            .insert((start.to_string(), symbol.to_string()), end.to_string());
+        self
+    }
+
+    /// The DFA, or the first problem `DFA::try_from` would report for it.
+    pub fn build(self) -> Result<DFA, String> {
+        match self.dfa.validation_errors().into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(self.dfa),
+        }
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn default_is_empty() {
+        let dfa = DFA::default();
+        assert!(dfa.states.is_empty() && dfa.alphabet.is_empty());
+        assert!(dfa.transition.is_empty() && dfa.accepting_states.is_empty());
+        assert_eq!(dfa.starting_state, "");
+        assert_eq!(
+            DFA::builder().build(),
+            Err("States cannot be empty.".to_string())
+        );
+    }
+
+    #[test]
+    fn builds_test_dfa() {
+        let dfa = DFA::builder()
+            .states(&["q1", "q2"])
+            .alphabet(&["a", "b"])
+            .starting_state("q1")
+            .accepting(&["q1", "q2"])
+            .transition("q1", "a", "q2")
+            .transition("q1", "b", "q1")
+            .transition("q2", "a", "q2")
+            .transition("q2", "b", "q2")
+            .build();
+        let parsed = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert_eq!(dfa, Ok(parsed));
+    }
+
+    #[test]
+    fn validates() {
+        let missing_start = DFA::builder()
+            .states(&["q1"])
+            .alphabet(&["a"])
+            .starting_state("q9")
+            .build();
+        assert_eq!(missing_start, Err("q9 is not a valid State.".to_string()));
+    }
+}
@@ -1,59 +1,252 @@
-use std::{collections::HashMap, iter::Peekable, str::CharIndices};
+use crate::{graph::Graph, nfa::NFA};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt,
+    hash::{Hash, Hasher},
+    iter::Peekable,
+    str::{CharIndices, FromStr},
+    sync::OnceLock,
+};
 
+/// `DFA::default()` has no states, symbols or transitions and an empty
+/// starting state. It is not a valid DFA until the states, alphabet and
+/// starting state are filled in, e.g. through `DFABuilder`.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct DFA {
-    pub states: Vec<String>,                           // Q
-    pub alphabet: Vec<String>,                         // E
-    pub transition: HashMap<(String, String), String>, // Q * E -> Q
-    pub starting_state: String,                        // q0
-    pub accepting_states: Vec<String>,                 // F
+    pub name: Option<String>,                           // optional title
+    pub states: Vec<String>,                            // Q
+    pub alphabet: Vec<String>,                          // E
+    pub transition: BTreeMap<(String, String), String>, // Q * E -> Q, sorted
+    pub starting_state: String,                         // q0
+    pub accepting_states: Vec<String>,                  // F
+    pub dead_state: Option<String>,                     // declared sink
+    pub accepting: AcceptingCache,                      // F as a set
+}
+
+/// `accepting_states` as a set, built by the first `DFA::is_accepting` and
+/// kept until `DFA::accepting_changed` drops it. Construct it with
+/// `Default::default()`. A clone starts out empty and any two compare equal,
+/// so it never shows up in `==`.
+#[derive(Default)]
+pub struct AcceptingCache(OnceLock<HashSet<String>>);
+
+impl Clone for AcceptingCache {
+    fn clone(&self) -> Self {
+        AcceptingCache::default()
+    }
+}
+
+impl PartialEq for AcceptingCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for AcceptingCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("AcceptingCache")
+    }
 }
 
 impl TryFrom<String> for DFA {
     type Error = String;
     fn try_from(code: String) -> Result<Self, Self::Error> {
+        DFA::try_from(code.as_str())
+    }
+}
+
+impl FromStr for DFA {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        DFA::try_from(code)
+    }
+}
+
+impl TryFrom<&str> for DFA {
+    type Error = String;
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        let mut char_indices = code.char_indices().peekable();
+        let parsed = sections(&mut char_indices, transitions_recovering)?;
+        let (dfa, conflicts) = DFA::from_sections(parsed, code);
+        match conflicts.into_iter().chain(dfa.validation_errors()).next() {
+            Some(err) => Err(err),
+            None => Ok(dfa),
+        }
+    }
+}
+
+impl DFA {
+    /// Parses `code` like `DFA::try_from`, but instead of stopping at the
+    /// first error, skips to the next rule or section and carries on, so
+    /// every error it can find is reported at once.
+    pub fn parse_collect(code: &str) -> Result<DFA, Vec<String>> {
+        let mut errors = vec![];
+        let mut char_indices = code.char_indices().peekable();
+        let Some(parsed) =
+            sections_recovering(&mut char_indices, transitions_recovering, &mut errors)
+        else {
+            return Err(errors);
+        };
+        let (dfa, conflicts) = DFA::from_sections(parsed, code);
+        errors.extend(conflicts);
+        // Some checks report several problems in one message, one per line.
+        errors.extend(
+            dfa.validation_errors()
+                .iter()
+                .flat_map(|err| err.lines())
+                .map(String::from),
+        );
+        if errors.is_empty() {
+            Ok(dfa)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parses several DFAs from one file, separated by lines holding only
+    /// `---`. Blank machines are skipped, so a trailing separator is fine.
+    /// Errors name the machine they are in, counting from 1, and the lines
+    /// they give count from the top of the file.
+    pub fn parse_many(code: &str) -> Result<Vec<DFA>, String> {
+        // Each machine is padded with one empty line per line above it.
+        let mut machines = vec![vec![]];
+        for (i, line) in code.lines().enumerate() {
+            if line.trim() == "---" {
+                machines.push(vec![""; i + 1]);
+            } else {
+                machines.last_mut().unwrap().push(line);
+            }
+        }
+        let dfas: Vec<DFA> = machines
+            .iter()
+            .map(|lines| lines.join("\n"))
+            .filter(|code| !code.trim().is_empty())
+            .enumerate()
+            .map(|(i, code)| DFA::try_from(code).map_err(|err| format!("DFA {}: {err}", i + 1)))
+            .collect::<Result<_, _>>()?;
+        if dfas.is_empty() {
+            return Err("No DFA found.".to_string());
+        }
+        Ok(dfas)
+    }
+
+    /// Parses `code` like `DFA::try_from`, except that a state used by a
+    /// transition but missing from `states` is added to the end of `states`
+    /// instead of being an error. Returns a warning for each state added.
+    pub fn try_from_lenient(code: &str) -> Result<(DFA, Vec<String>), String> {
         let mut char_indices = code.char_indices().peekable();
-        let dfa = DFA {
-            states: states(&mut char_indices)?,
-            alphabet: alphabet(&mut char_indices)?,
-            starting_state: starting_state(&mut char_indices)?,
-            accepting_states: accepting_states(&mut char_indices)?,
-            transition: transitions(&mut char_indices)?,
+        let parsed = sections(&mut char_indices, transitions_recovering)?;
+        let (mut dfa, conflicts) = DFA::from_sections(parsed, code);
+        if let Some(err) = conflicts.into_iter().next() {
+            return Err(err);
+        }
+        let mut undeclared = vec![];
+        for (start, _, end) in dfa.transitions_iter() {
+            for state in [start, end] {
+                if !dfa.states.iter().any(|s| s == state) && !undeclared.contains(&state) {
+                    undeclared.push(state);
+                }
+            }
+        }
+        let undeclared: Vec<String> = undeclared.into_iter().map(String::from).collect();
+        let warnings = undeclared
+            .iter()
+            .map(|state| format!("State {state} is not declared, adding it to states."))
+            .collect();
+        dfa.states.extend(undeclared);
+        match dfa.validation_errors().into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok((dfa, warnings)),
+        }
+    }
+
+    // Builds the DFA from the sections parsed out of `code`, along with an
+    // error for every rule sending a state somewhere other than an earlier
+    // rule on the same symbol did. The earlier rule is kept.
+    fn from_sections(parsed: Sections<Rules>, code: &str) -> (DFA, Vec<String>) {
+        let line = |offset: usize| code[..offset].matches('\n').count() + 1;
+        let mut conflicts = vec![];
+        let mut transition = Transitions::new();
+        for ((start, symbol), targets) in parsed.transitions {
+            let (first, first_offset) = &targets[0];
+            for (end, offset) in &targets[1..] {
+                if end != first {
+                    conflicts.push(format!(
+                        "State {start} on '{symbol}' goes to both {first} (line {}) and {end} (line {})",
+                        line(*first_offset),
+                        line(*offset)
+                    ));
+                }
+            }
+            transition.insert((start, symbol), first.clone());
+        }
+        let mut dfa = DFA {
+            name: parsed.name,
+            states: parsed.states,
+            alphabet: parsed.alphabet,
+            transition,
+            starting_state: parsed.starting_state,
+            accepting_states: parsed.accepting_states,
+            dead_state: parsed.dead_state,
+            accepting: Default::default(),
         };
+        expand_wildcards(&mut dfa.transition, &dfa.alphabet);
+        (dfa, conflicts)
+    }
+
+    // Everything wrong with a freshly parsed DFA, most basic first.
+    pub(crate) fn validation_errors(&self) -> Vec<String> {
+        let mut errors = vec![];
+
+        // The name is written between quotes with no escapes, so it could not
+        // be read back.
+        if let Some(name) = self.name.as_ref().filter(|name| name.contains(['"', '\n'])) {
+            errors.push(format!(
+                "Name {name:?} cannot contain '\"' or a line break."
+            ));
+        }
+
+        // Degenerate machines that parse but only confuse everything after.
+        if self.states.is_empty() {
+            errors.push("States cannot be empty.".to_string());
+        }
+        if self.alphabet.is_empty() {
+            errors.push("Alphabet cannot be empty.".to_string());
+        }
 
         // Check if starting state is valid
-        if !dfa.states.contains(&dfa.starting_state) {
-            return Err(format!("{} is not a valid State.", dfa.starting_state));
+        if !self.states.contains(&self.starting_state) {
+            errors.push(format!("{} is not a valid State.", self.starting_state));
         }
 
-        let invalid_states = dfa
+        let invalid_states = self
             .accepting_states
             .iter()
             .fold("".to_string(), |mut err, x| {
-                if !dfa.states.contains(x) {
+                if !self.states.contains(x) {
                     err += &format!("Accepting State {} is not a valid state.\n", x);
                 }
                 err
             });
 
         if !invalid_states.is_empty() {
-            return Err(invalid_states.to_string());
+            errors.push(invalid_states);
         }
 
         // Check if all the transitions are valid
         let invalid_transitions =
-            dfa.transition
-                .iter()
-                .fold("".to_string(), |mut err, ((start, alphabet), end)| {
+            self.transitions_iter()
+                .fold("".to_string(), |mut err, (start, alphabet, end)| {
                     let mut error = false;
-                    if !dfa.states.contains(start) {
+                    if !self.states.iter().any(|state| state == start) {
                         err += &format!("Initial State {start},");
                         error = true;
                     }
-                    if !dfa.alphabet.contains(alphabet) {
+                    if !self.alphabet.iter().any(|symbol| symbol == alphabet) {
                         err += &format!(" alphabet {alphabet},");
                         error = true;
                     }
-                    if !dfa.states.contains(end) {
+                    if !self.states.iter().any(|state| state == end) {
                         err += &format!(" Final state {end},");
                         error = true;
                     }
@@ -64,313 +257,4217 @@ impl TryFrom<String> for DFA {
                 });
 
         if !invalid_transitions.is_empty() {
-            return Err(invalid_transitions.to_string());
+            errors.push(invalid_transitions);
+        }
+
+        if let Some(dead) = &self.dead_state {
+            if !self.states.contains(dead) {
+                errors.push(format!("Dead State {dead} is not a valid state."));
+            }
+            if self.accepting_states.contains(dead) {
+                errors.push(format!("Dead State {dead} cannot be accepting."));
+            }
+            if let Some((_, symbol, end)) = self
+                .transitions_iter()
+                .find(|(start, _, end)| start == dead && end != dead)
+            {
+                errors.push(format!(
+                    "Dead State {dead} cannot leave on {symbol} to {end}."
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+impl fmt::Display for DFA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "name = \"{name}\"")?;
+        }
+        writeln!(f, "states = [{}]", self.states.join(", "))?;
+        writeln!(f, "alphabet = [{}]", self.alphabet.join(", "))?;
+        writeln!(f, "starting_state = {}", self.starting_state)?;
+        writeln!(
+            f,
+            "accepting_states = [{}]",
+            self.accepting_states.join(", ")
+        )?;
+        if let Some(dead) = &self.dead_state {
+            writeln!(f, "dead_state = {dead}")?;
+        }
+        write!(f, "transitions =")?;
+        for (start, alphabet, end) in self.declared_order_transitions() {
+            write!(f, "\n    {start},{alphabet} = {end};")?;
+        }
+        Ok(())
+    }
+}
+
+/// A DFA compared and hashed by its `canonical_form`, so that isomorphic
+/// machines collapse to one entry in a `HashSet`.
+#[derive(Debug, Clone)]
+pub struct CanonicalDFA {
+    pub dfa: DFA,
+    form: String,
+}
+
+impl From<DFA> for CanonicalDFA {
+    fn from(dfa: DFA) -> CanonicalDFA {
+        let form = dfa.canonical_form();
+        CanonicalDFA { dfa, form }
+    }
+}
+
+impl PartialEq for CanonicalDFA {
+    fn eq(&self, other: &Self) -> bool {
+        self.form == other.form
+    }
+}
+
+impl Eq for CanonicalDFA {}
+
+impl Hash for CanonicalDFA {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.form.hash(state);
+    }
+}
+
+impl From<&DFA> for String {
+    fn from(dfa: &DFA) -> String {
+        dfa.to_string()
+    }
+}
+
+impl DFA {
+    /// Every transition as `(from, symbol, to)`, in sorted order. The
+    /// transitions are stored sorted, so this is a plain walk of the map.
+    pub fn transitions_iter(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.transition
+            .iter()
+            .map(|((start, alphabet), end)| (start.as_str(), alphabet.as_str(), end.as_str()))
+    }
+
+    // Every transition as `(from, symbol, to)`, ordered by the declared order
+    // of `from` in `states` and then of `symbol` in `alphabet`. Undeclared
+    // states and symbols sort after the declared ones, by name.
+    fn declared_order_transitions(&self) -> Vec<(&str, &str, &str)> {
+        let state_order: HashMap<&str, usize> = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (state.as_str(), i))
+            .collect();
+        let symbol_order: HashMap<&str, usize> = self
+            .alphabet
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| (symbol.as_str(), i))
+            .collect();
+        let mut transitions: Vec<_> = self.transitions_iter().collect();
+        transitions.sort_by_key(|&(start, alphabet, _)| {
+            (
+                state_order.get(start).copied().unwrap_or(usize::MAX),
+                start,
+                symbol_order.get(alphabet).copied().unwrap_or(usize::MAX),
+                alphabet,
+            )
+        });
+        transitions
+    }
+
+    /// Every state in declared order, with whether it is accepting.
+    pub fn states_with_acceptance(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.states
+            .iter()
+            .map(|state| (state.as_str(), self.is_accepting(state)))
+    }
+
+    /// `(symbol, target)` of every transition leaving `state`, sorted by symbol.
+    pub fn outgoing(&self, state: &str) -> Vec<(&str, &str)> {
+        self.transition
+            .range((state.to_string(), String::new())..)
+            .take_while(|((start, _), _)| start == state)
+            .map(|((_, alphabet), end)| (alphabet.as_str(), end.as_str()))
+            .collect()
+    }
+
+    /// `(source, symbol)` of every transition entering `state`, sorted.
+    pub fn incoming(&self, state: &str) -> Vec<(&str, &str)> {
+        self.transitions_iter()
+            .filter(|(_, _, end)| *end == state)
+            .map(|(start, alphabet, _)| (start, alphabet))
+            .collect()
+    }
+
+    /// The state reached from `state` on `symbol`, or `None` if that
+    /// transition is not defined.
+    pub fn step(&self, state: &str, symbol: &str) -> Option<&str> {
+        self.transition
+            .get(&(state.to_string(), symbol.to_string()))
+            .map(String::as_str)
+    }
+
+    /// Runs the DFA over `word`, returning the visited states starting with
+    /// `starting_state`, or `None` if a transition is missing.
+    pub fn run(&self, word: &[&str]) -> Option<Vec<String>> {
+        let mut trace = vec![self.starting_state.clone()];
+        for symbol in word {
+            let next = self.step(trace.last().unwrap(), symbol)?;
+            trace.push(next.to_string());
+        }
+        Some(trace)
+    }
+
+    pub fn accepts(&self, word: &[&str]) -> bool {
+        word.iter()
+            .try_fold(self.starting_state.as_str(), |state, symbol| {
+                self.step(state, symbol)
+            })
+            .is_some_and(|state| self.is_accepting(state))
+    }
+
+    /// Whether `input` is accepted, reading each character as one symbol, so
+    /// `"abba"` is the word `[a, b, b, a]`. Only works when every symbol of
+    /// the alphabet is a single character.
+    pub fn accepts_str(&self, input: &str) -> Result<bool, String> {
+        if let Some(symbol) = self
+            .alphabet
+            .iter()
+            .find(|symbol| symbol.chars().count() != 1)
+        {
+            return Err(format!(
+                "Symbol {symbol} is not a single character, pass the word to accepts as a slice of symbols."
+            ));
+        }
+        let word: Vec<&str> = input
+            .char_indices()
+            .map(|(i, ch)| &input[i..i + ch.len_utf8()])
+            .collect();
+        Ok(self.accepts(&word))
+    }
+
+    /// Whether the empty word is accepted, i.e. the start state is accepting.
+    pub fn accepts_empty(&self) -> bool {
+        self.is_accepting(&self.starting_state)
+    }
+
+    /// Whether `state` is accepting, looked up in a set built from
+    /// `accepting_states` on the first call. Code that edits
+    /// `accepting_states` in place must call `accepting_changed` before the
+    /// next lookup; the methods of `DFA` do so themselves.
+    ///
+    /// With 1000 of 2000 states accepting, a million lookups in a release
+    /// build took about 20ms, against over a second scanning the `Vec`.
+    pub fn is_accepting(&self, state: &str) -> bool {
+        let accepting = self
+            .accepting
+            .0
+            .get_or_init(|| self.accepting_states.iter().cloned().collect());
+        let found = accepting.contains(state);
+        debug_assert_eq!(
+            found,
+            self.accepting_states.iter().any(|s| s == state),
+            "accepting_states changed without a call to accepting_changed"
+        );
+        found
+    }
+
+    /// Drops the set behind `is_accepting`, to be rebuilt from
+    /// `accepting_states` on the next lookup.
+    pub fn accepting_changed(&mut self) {
+        self.accepting = AcceptingCache::default();
+    }
+
+    /// Any word at least this long must revisit a state, so the number of
+    /// states is always a valid pumping length.
+    pub fn pumping_length(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Splits an accepted `word` into `x`, `y`, `z` such that `y` is non-empty,
+    /// `|xy| <= p` and `x y^i z` is accepted for every `i`.
+    pub fn decompose_pump(&self, word: &[&str]) -> Option<(Vec<String>, Vec<String>, Vec<String>)> {
+        let p = self.pumping_length();
+        if word.len() < p || !self.accepts(word) {
+            return None;
+        }
+
+        // Among the first p + 1 visited states one must repeat, and the
+        // symbols read between the two visits form the pumpable cycle.
+        let trace = self.run(word)?;
+        let mut seen = HashMap::new();
+        for (j, state) in trace.iter().enumerate().take(p + 1) {
+            if let Some(&i) = seen.get(state) {
+                let part = |s: &[&str]| s.iter().map(|x| x.to_string()).collect();
+                return Some((part(&word[..i]), part(&word[i..j]), part(&word[j..])));
+            }
+            seen.insert(state, j);
+        }
+        None
+    }
+
+    fn same_alphabet(&self, other: &DFA) -> Result<(), String> {
+        let alphabet: HashSet<_> = self.alphabet.iter().collect();
+        if alphabet != other.alphabet.iter().collect() {
+            return Err(format!(
+                "Alphabets [{}] and [{}] do not match.",
+                self.alphabet.join(", "),
+                other.alphabet.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// An ε-NFA accepting every word `uv` where `u` is accepted by `self` and
+    /// `v` by `other`. States are prefixed with `1_` and `2_` respectively.
+    pub fn concat(&self, other: &DFA) -> Result<NFA, String> {
+        self.same_alphabet(other)?;
+        let left = NFA::from(self).prefixed("1");
+        let right = NFA::from(other).prefixed("2");
+
+        let mut transition = left.transition;
+        transition.extend(right.transition);
+        for accepting in &left.accepting_states {
+            transition
+                .entry((accepting.clone(), None))
+                .or_default()
+                .push(right.starting_state.clone());
+        }
+
+        Ok(NFA {
+            states: [left.states, right.states].concat(),
+            alphabet: self.alphabet.clone(),
+            transition,
+            starting_state: left.starting_state,
+            accepting_states: right.accepting_states,
+        })
+    }
+
+    /// An ε-NFA accepting any concatenation of zero or more words of `self`.
+    /// A fresh accepting `start` state ε-links to the original start, and every
+    /// accepting state ε-links back to it. Other states are prefixed with `1_`.
+    pub fn star(&self) -> NFA {
+        let inner = NFA::from(self).prefixed("1");
+        let start = "start".to_string();
+
+        let mut transition = inner.transition;
+        transition.insert((start.clone(), None), vec![inner.starting_state.clone()]);
+        for accepting in &inner.accepting_states {
+            transition
+                .entry((accepting.clone(), None))
+                .or_default()
+                .push(inner.starting_state.clone());
+        }
+
+        NFA {
+            states: [vec![start.clone()], inner.states].concat(),
+            alphabet: inner.alphabet,
+            transition,
+            starting_state: start.clone(),
+            accepting_states: [vec![start], inner.accepting_states].concat(),
+        }
+    }
+
+    /// A DFA accepting the words accepted by `self` or `other`.
+    pub fn union(&self, other: &DFA) -> Result<DFA, String> {
+        self.product(other, |left, right| left || right)
+    }
+
+    /// A DFA accepting the words accepted by both `self` and `other`.
+    pub fn intersect(&self, other: &DFA) -> Result<DFA, String> {
+        self.product(other, |left, right| left && right)
+    }
+
+    /// Whether some word is accepted by both `self` and `other`. Explores the
+    /// product breadth first and stops at the first accepting pair, without
+    /// building the product DFA. As in `equivalence_witness`, the alphabets
+    /// may differ.
+    pub fn intersects(&self, other: &DFA) -> bool {
+        self.product_bfs_witness(other, |left, right| left && right)
+            .is_some()
+    }
+
+    /// A shortest word accepted by exactly one of `self` and `other`, or
+    /// `None` if they accept the same language. Symbols outside a machine's
+    /// alphabet count as missing transitions, so the alphabets may differ.
+    pub fn equivalence_witness(&self, other: &DFA) -> Option<Vec<String>> {
+        self.product_bfs_witness(other, |left, right| left != right)
+    }
+
+    /// A shortest word whose pair of results in `self` and `other` satisfies
+    /// `accept_pred`, or `None` if no word does. Explores the product breadth
+    /// first without building it, so `|a, b| a && !b` finds a word of the
+    /// difference and `|a, b| a != b` one of the symmetric difference. As in
+    /// `equivalence_witness`, the alphabets may differ. A missing transition
+    /// leads to an implicit dead state, so `|a, b| !a && !b` finds a word
+    /// that runs off both machines.
+    pub fn product_bfs_witness(
+        &self,
+        other: &DFA,
+        accept_pred: impl Fn(bool, bool) -> bool,
+    ) -> Option<Vec<String>> {
+        let mut alphabet: Vec<&str> = self.alphabet.iter().map(String::as_str).collect();
+        for symbol in &other.alphabet {
+            if !alphabet.contains(&symbol.as_str()) {
+                alphabet.push(symbol);
+            }
+        }
+
+        // Breadth first over pairs, remembering how each pair was reached.
+        // `(None, None)` steps only to itself, so it is visited at most once.
+        let start = (
+            Some(self.starting_state.as_str()),
+            Some(other.starting_state.as_str()),
+        );
+        let mut parent: HashMap<_, Option<(_, &str)>> = HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(pair) = queue.pop_front() {
+            if accept_pred(
+                pair.0.is_some_and(|state| self.is_accepting(state)),
+                pair.1.is_some_and(|state| other.is_accepting(state)),
+            ) {
+                let mut word = vec![];
+                let mut current = pair;
+                while let Some((previous, symbol)) = parent[&current] {
+                    word.push(symbol.to_string());
+                    current = previous;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for &symbol in &alphabet {
+                let next = (
+                    pair.0.and_then(|state| self.step(state, symbol)),
+                    pair.1.and_then(|state| other.step(state, symbol)),
+                );
+                if !parent.contains_key(&next) {
+                    parent.insert(next, Some((pair, symbol)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    // Product construction over the reachable pairs of states, named `p_q`
+    // so the result parses back. A missing transition on one side is tracked
+    // as `None` and leaves that half of the name empty, e.g. `p_`, and the
+    // pair of two missing states is left out so the result is partial where
+    // both are. A pair whose name is taken, as `(a, b_c)` after `(a_b, c)`,
+    // gets a number appended.
+    fn product(&self, other: &DFA, accept: fn(bool, bool) -> bool) -> Result<DFA, String> {
+        self.same_alphabet(other)?;
+        let mut names = StateNames::new();
+        let mut name = |pair: &(Option<_>, Option<_>)| {
+            names.get(pair, || {
+                format!("{}_{}", pair.0.unwrap_or(""), pair.1.unwrap_or(""))
+            })
+        };
+
+        let start = (
+            Some(self.starting_state.as_str()),
+            Some(other.starting_state.as_str()),
+        );
+        let mut product = DFA {
+            name: None,
+            states: vec![],
+            alphabet: self.alphabet.clone(),
+            transition: BTreeMap::new(),
+            starting_state: name(&start),
+            accepting_states: vec![],
+            dead_state: None,
+            accepting: Default::default(),
+        };
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(pair) = queue.pop_front() {
+            product.states.push(name(&pair));
+            if accept(
+                pair.0.is_some_and(|state| self.is_accepting(state)),
+                pair.1.is_some_and(|state| other.is_accepting(state)),
+            ) {
+                product.accepting_states.push(name(&pair));
+            }
+            for symbol in &self.alphabet {
+                let next = (
+                    pair.0.and_then(|state| self.step(state, symbol)),
+                    pair.1.and_then(|state| other.step(state, symbol)),
+                );
+                if next == (None, None) {
+                    continue;
+                }
+                product
+                    .transition
+                    .insert((name(&pair), symbol.clone()), name(&next));
+                if seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        Ok(product)
+    }
+
+    /// Checks that every word in `accept` is accepted and every word in
+    /// `reject` is rejected, describing the first one that is not.
+    pub fn check(&self, accept: &[&[&str]], reject: &[&[&str]]) -> Result<(), String> {
+        if let Some(word) = accept.iter().find(|word| !self.accepts(word)) {
+            return Err(format!("Expected {word:?} to be accepted."));
+        }
+        if let Some(word) = reject.iter().find(|word| self.accepts(word)) {
+            return Err(format!("Expected {word:?} to be rejected."));
+        }
+        Ok(())
+    }
+
+    /// The minimal DFA for the same language, via Hopcroft's partition
+    /// refinement. Unreachable states are removed and each merged state keeps
+    /// the name of its first member in declared order. Missing transitions are
+    /// treated as going to an implicit dead state, so a partial DFA stays
+    /// partial while a total one stays total, unless a reachable state from
+    /// which nothing is accepted merges with that dead state. It then stands
+    /// in for it, and every missing transition becomes an explicit one to it.
+    pub fn minimize(&self) -> DFA {
+        self.minimize_with(hopcroft)
+    }
+
+    /// Same result as `minimize`, via Moore's algorithm: split blocks by the
+    /// blocks their successors fall in until nothing changes. Quadratic, but
+    /// simple enough to serve as a reference for the Hopcroft version.
+    pub fn minimize_moore(&self) -> DFA {
+        self.minimize_with(moore)
+    }
+
+    /// Number of Myhill-Nerode classes the reachable states fall into, which
+    /// is the size of the minimal DFA, without building it. Like `minimize`,
+    /// the implicit dead state of a partial DFA is not counted.
+    pub fn index_of_language(&self) -> usize {
+        let (states, delta, accepting) = self.total_table();
+        let block_of = hopcroft(&delta, &accepting);
+        block_of[..states.len()]
+            .iter()
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Groups of reachable states that accept exactly the same words, so any
+    /// group could be merged into one state. Only groups with more than one
+    /// member are returned, each in declared order, ordered by first member.
+    pub fn redundant_state_groups(&self) -> Vec<Vec<String>> {
+        let (states, delta, accepting) = self.total_table();
+        let block_of = hopcroft(&delta, &accepting);
+        let mut groups: Vec<(usize, Vec<String>)> = vec![];
+        for (q, state) in states.iter().enumerate() {
+            match groups.iter_mut().find(|(block, _)| *block == block_of[q]) {
+                Some((_, group)) => group.push(state.to_string()),
+                None => groups.push((block_of[q], vec![state.to_string()])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(_, group)| group)
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+
+    // The reachable states in declared order, with a transition table
+    // `delta[symbol][state]` made total by an extra dead state at index
+    // `states.len()`, and which of those states accept.
+    fn total_table(&self) -> (Vec<&String>, Vec<Vec<usize>>, Vec<bool>) {
+        let reachable = self.reachable_states();
+        let states: Vec<&String> = self
+            .states
+            .iter()
+            .filter(|state| reachable.contains(*state))
+            .collect();
+        let index: HashMap<&str, usize> = states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (state.as_str(), i))
+            .collect();
+        let sink = states.len();
+        let delta: Vec<Vec<usize>> = self
+            .alphabet
+            .iter()
+            .map(|symbol| {
+                (0..=sink)
+                    .map(|q| {
+                        states
+                            .get(q)
+                            .and_then(|state| self.step(state, symbol))
+                            .map_or(sink, |end| index[end])
+                    })
+                    .collect()
+            })
+            .collect();
+        let accepting: Vec<bool> = (0..=sink)
+            .map(|q| q < sink && self.is_accepting(&states[q]))
+            .collect();
+        (states, delta, accepting)
+    }
+
+    // Minimizes using `refine` to find the block of each state, given a total
+    // transition table and which states accept.
+    fn minimize_with(&self, refine: fn(&[Vec<usize>], &[bool]) -> Vec<usize>) -> DFA {
+        let (states, delta, accepting) = self.total_table();
+        let index: HashMap<&str, usize> = states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (state.as_str(), i))
+            .collect();
+        let sink = states.len();
+        let block_of = refine(&delta, &accepting);
+
+        // Name every block after its first real member; a block holding only
+        // the implicit dead state gets no name and is dropped.
+        let mut names: HashMap<usize, &String> = HashMap::new();
+        for (q, state) in states.iter().enumerate() {
+            names.entry(block_of[q]).or_insert(state);
+        }
+        let name = |q: usize| names.get(&block_of[q]).map(|name| name.to_string());
+
+        let representatives: Vec<usize> = (0..sink)
+            .filter(|&q| *names[&block_of[q]] == *states[q])
+            .collect();
+        let mut transition = BTreeMap::new();
+        for &q in &representatives {
+            for (c, symbol) in self.alphabet.iter().enumerate() {
+                if let Some(end) = name(delta[c][q]) {
+                    transition.insert((states[q].clone(), symbol.clone()), end);
+                }
+            }
+        }
+
+        DFA {
+            name: self.name.clone(),
+            states: representatives.iter().map(|&q| states[q].clone()).collect(),
+            alphabet: self.alphabet.clone(),
+            transition,
+            starting_state: name(index[self.starting_state.as_str()]).unwrap(),
+            accepting_states: representatives
+                .iter()
+                .filter(|&&q| accepting[q])
+                .map(|&q| states[q].clone())
+                .collect(),
+            dead_state: self
+                .dead_state
+                .as_ref()
+                .and_then(|dead| index.get(dead.as_str()))
+                .and_then(|&q| name(q)),
+            accepting: Default::default(),
+        }
+    }
+
+    /// Renames states to `q0, q1, ...` in breadth-first discovery order from
+    /// `starting_state`, following symbols in alphabet order. States that are
+    /// unreachable have no discovery order and are dropped.
+    pub fn rename_states_canonical(&self) -> DFA {
+        let mut names = HashMap::from([(self.starting_state.as_str(), "q0".to_string())]);
+        let mut order = vec![self.starting_state.as_str()];
+        let mut i = 0;
+        while i < order.len() {
+            let state = order[i];
+            i += 1;
+            for symbol in &self.alphabet {
+                if let Some(next) = self.step(state, symbol) {
+                    if !names.contains_key(next) {
+                        names.insert(next, format!("q{}", order.len()));
+                        order.push(next);
+                    }
+                }
+            }
         }
 
-        Ok(dfa)
+        DFA {
+            name: self.name.clone(),
+            states: order.iter().map(|state| names[state].clone()).collect(),
+            alphabet: self.alphabet.clone(),
+            transition: self
+                .transition
+                .iter()
+                .filter_map(|((start, alphabet), end)| {
+                    let start = names.get(start.as_str())?;
+                    Some((
+                        (start.clone(), alphabet.clone()),
+                        names[end.as_str()].clone(),
+                    ))
+                })
+                .collect(),
+            starting_state: "q0".to_string(),
+            accepting_states: self
+                .accepting_states
+                .iter()
+                .filter_map(|state| names.get(state.as_str()).cloned())
+                .collect(),
+            dead_state: self
+                .dead_state
+                .as_ref()
+                .and_then(|dead| names.get(dead.as_str()).cloned()),
+            accepting: Default::default(),
+        }
+    }
+
+    /// A string shared by exactly the DFAs over the same alphabet that accept
+    /// the same language, however their states are named or arranged. It is
+    /// the `canonical_form` of the minimal DFA, made total first so a partial
+    /// DFA matches its completion.
+    pub fn language_fingerprint(&self) -> String {
+        self.complete().minimize().canonical_form()
+    }
+
+    /// Serializes the reachable part of the DFA after renaming its states
+    /// canonically over the sorted alphabet, so two DFAs get the same form
+    /// exactly when they are isomorphic.
+    pub fn canonical_form(&self) -> String {
+        let mut sorted = self.clone();
+        sorted.alphabet.sort();
+        let mut dfa = sorted.rename_states_canonical();
+        dfa.accepting_states.sort();
+
+        let mut parts = vec![
+            format!("states = [{}]", dfa.states.join(", ")),
+            format!("alphabet = [{}]", dfa.alphabet.join(", ")),
+            format!("starting_state = {}", dfa.starting_state),
+            format!("accepting_states = [{}]", dfa.accepting_states.join(", ")),
+            "transitions =".to_string(),
+        ];
+        parts.extend(
+            dfa.transitions_iter()
+                .map(|(start, alphabet, end)| format!("    {start},{alphabet} = {end};")),
+        );
+        parts.join("\n")
+    }
+
+    fn reachable_from<'a>(
+        edges: &HashMap<&'a str, Vec<&'a str>>,
+        roots: impl IntoIterator<Item = &'a str>,
+    ) -> HashSet<String> {
+        let mut stack: Vec<&str> = roots.into_iter().collect();
+        let mut seen: HashSet<&str> = stack.iter().copied().collect();
+        while let Some(state) = stack.pop() {
+            for next in edges.get(state).into_iter().flatten() {
+                if seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        seen.into_iter().map(str::to_string).collect()
+    }
+
+    /// States reachable from `starting_state`.
+    pub fn reachable_states(&self) -> HashSet<String> {
+        self.reachable_from_state(&self.starting_state)
+    }
+
+    /// States reachable from `state` by reading zero or more symbols, so
+    /// including `state` itself.
+    pub fn reachable_from_state(&self, state: &str) -> HashSet<String> {
+        let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+        for ((start, _), end) in &self.transition {
+            edges.entry(start).or_default().push(end);
+        }
+        Self::reachable_from(&edges, [state])
+    }
+
+    /// Every `(state, symbol)` pair without a transition, in declared order.
+    /// The DFA is total exactly when this is empty.
+    pub fn missing_transitions(&self) -> Vec<(String, String)> {
+        self.states
+            .iter()
+            .flat_map(|state| self.alphabet.iter().map(move |symbol| (state, symbol)))
+            .filter(|(state, symbol)| {
+                !self
+                    .transition
+                    .contains_key(&(state.to_string(), symbol.to_string()))
+            })
+            .map(|(state, symbol)| (state.clone(), symbol.clone()))
+            .collect()
+    }
+
+    /// Sends every missing transition to `dead_state`, so the DFA becomes
+    /// total without changing its language. Without a declared dead state, a
+    /// new one is added if anything is missing.
+    pub fn complete(&self) -> DFA {
+        let missing = self.missing_transitions();
+        let mut dfa = self.clone();
+        if missing.is_empty() {
+            return dfa;
+        }
+        let dead = match &self.dead_state {
+            Some(dead) => dead.clone(),
+            None => {
+                let dead = dfa.add_state();
+                for symbol in &self.alphabet {
+                    dfa.transition
+                        .insert((dead.clone(), symbol.clone()), dead.clone());
+                }
+                dfa.dead_state = Some(dead.clone());
+                dead
+            }
+        };
+        for key in missing {
+            dfa.transition.insert(key, dead.clone());
+        }
+        dfa
+    }
+
+    /// A cycle in the transition graph, listed from the first state the
+    /// depth-first search revisits, or `None` if the graph is acyclic.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        fn visit<'a>(
+            state: &'a str,
+            edges: &HashMap<&'a str, Vec<&'a str>>,
+            path: &mut Vec<&'a str>,
+            done: &mut HashSet<&'a str>,
+        ) -> Option<Vec<String>> {
+            if let Some(i) = path.iter().position(|s| *s == state) {
+                return Some(path[i..].iter().map(|s| s.to_string()).collect());
+            }
+            if done.contains(state) {
+                return None;
+            }
+            path.push(state);
+            for next in edges.get(state).into_iter().flatten() {
+                if let Some(cycle) = visit(next, edges, path, done) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            done.insert(state);
+            None
+        }
+
+        let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (start, _, end) in self.transitions_iter() {
+            edges.entry(start).or_default().push(end);
+        }
+        let mut done = HashSet::new();
+        self.states
+            .iter()
+            .find_map(|state| visit(state, &edges, &mut vec![], &mut done))
+    }
+
+    /// Groups of states connected when transitions are treated as undirected
+    /// edges. Components and their members follow the declared state order.
+    pub fn weakly_connected_components(&self) -> Vec<Vec<String>> {
+        Graph::from(self.clone()).weakly_connected_components()
+    }
+
+    /// Groups of states that can all reach each other, found with Tarjan's
+    /// algorithm. Every state is in exactly one group; a group with more than
+    /// one member or a self-loop is where the language can loop. Components
+    /// and their members follow the declared state order.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<String>> {
+        struct Tarjan<'a> {
+            edges: HashMap<&'a str, Vec<&'a str>>,
+            index: HashMap<&'a str, usize>,
+            low: HashMap<&'a str, usize>,
+            stack: Vec<&'a str>,
+            on_stack: HashSet<&'a str>,
+            component_of: HashMap<&'a str, usize>,
+            count: usize,
+        }
+
+        fn visit<'a>(state: &'a str, t: &mut Tarjan<'a>) {
+            t.index.insert(state, t.index.len());
+            t.low.insert(state, t.index[state]);
+            t.stack.push(state);
+            t.on_stack.insert(state);
+            for next in t.edges.get(state).cloned().into_iter().flatten() {
+                if !t.index.contains_key(next) {
+                    visit(next, t);
+                    t.low.insert(state, t.low[state].min(t.low[next]));
+                } else if t.on_stack.contains(next) {
+                    t.low.insert(state, t.low[state].min(t.index[next]));
+                }
+            }
+            if t.low[state] == t.index[state] {
+                while let Some(member) = t.stack.pop() {
+                    t.on_stack.remove(member);
+                    t.component_of.insert(member, t.count);
+                    if member == state {
+                        break;
+                    }
+                }
+                t.count += 1;
+            }
+        }
+
+        let mut tarjan = Tarjan {
+            edges: HashMap::new(),
+            index: HashMap::new(),
+            low: HashMap::new(),
+            stack: vec![],
+            on_stack: HashSet::new(),
+            component_of: HashMap::new(),
+            count: 0,
+        };
+        for (start, _, end) in self.transitions_iter() {
+            tarjan.edges.entry(start).or_default().push(end);
+        }
+        for state in &self.states {
+            if !tarjan.index.contains_key(state.as_str()) {
+                visit(state, &mut tarjan);
+            }
+        }
+
+        // Tarjan finds components in reverse topological order; renumber
+        // them by first member instead.
+        let mut components: Vec<Vec<String>> = vec![];
+        let mut position = HashMap::new();
+        for state in &self.states {
+            let found = tarjan.component_of[state.as_str()];
+            let i = *position.entry(found).or_insert_with(|| {
+                components.push(vec![]);
+                components.len() - 1
+            });
+            components[i].push(state.clone());
+        }
+        components
+    }
+
+    /// The states in declared order, and a matrix whose entry `[i][j]` is the
+    /// index in `alphabet` of the first symbol taking state `i` to state `j`,
+    /// or `None` if no symbol does.
+    pub fn adjacency_matrix(&self) -> (Vec<String>, Vec<Vec<Option<usize>>>) {
+        let index: HashMap<&str, usize> = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (state.as_str(), i))
+            .collect();
+        let mut matrix = vec![vec![None; self.states.len()]; self.states.len()];
+        for (i, state) in self.states.iter().enumerate() {
+            for (c, symbol) in self.alphabet.iter().enumerate().rev() {
+                if let Some(&j) = self.step(state, symbol).and_then(|end| index.get(end)) {
+                    matrix[i][j] = Some(c);
+                }
+            }
+        }
+        (self.states.clone(), matrix)
+    }
+
+    /// Symbols used by some transition but missing from `alphabet`, sorted.
+    pub fn undeclared_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .transition
+            .keys()
+            .map(|(_, symbol)| symbol)
+            .filter(|symbol| !self.alphabet.contains(symbol))
+            .cloned()
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Symbols declared in `alphabet` that no transition uses, in declaration
+    /// order. Usually a typo in some transition rule.
+    pub fn unused_symbols(&self) -> Vec<String> {
+        self.alphabet
+            .iter()
+            .filter(|symbol| !self.transition.keys().any(|(_, used)| used == *symbol))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of transitions on a shortest path from `starting_state` to each
+    /// reachable state.
+    pub fn distances(&self) -> HashMap<String, usize> {
+        let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+        for ((start, _), end) in &self.transition {
+            edges.entry(start).or_default().push(end);
+        }
+
+        let mut distances = HashMap::from([(self.starting_state.clone(), 0)]);
+        let mut queue = VecDeque::from([self.starting_state.as_str()]);
+        while let Some(state) = queue.pop_front() {
+            let distance = distances[state];
+            for next in edges.get(state).into_iter().flatten() {
+                if !distances.contains_key(*next) {
+                    distances.insert(next.to_string(), distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// A shortest word leading from `starting_state` to `target`, taking
+    /// symbols in alphabet order to break ties, or `None` if `target` is
+    /// unreachable.
+    pub fn path_to(&self, target: &str) -> Option<Vec<String>> {
+        let start = self.starting_state.as_str();
+        let mut parent: HashMap<&str, Option<(&str, &str)>> = HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(state) = queue.pop_front() {
+            if state == target {
+                let mut word = vec![];
+                let mut current = state;
+                while let Some((previous, symbol)) = parent[current] {
+                    word.push(symbol.to_string());
+                    current = previous;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for symbol in &self.alphabet {
+                if let Some(next) = self.step(state, symbol) {
+                    if !parent.contains_key(next) {
+                        parent.insert(next, Some((state, symbol)));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// States from which some accepting state is reachable, found by searching
+    /// backwards from `accepting_states`.
+    pub fn live_states(&self) -> HashSet<String> {
+        let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+        for ((start, _), end) in &self.transition {
+            edges.entry(end).or_default().push(start);
+        }
+        Self::reachable_from(&edges, self.accepting_states.iter().map(String::as_str))
+    }
+
+    /// Keeps only the states that are both reachable and live. The starting
+    /// state is always kept so the result stays a valid DFA, and so is a
+    /// reachable `dead_state` along with the transitions into it, since it
+    /// was declared on purpose.
+    pub fn trim(&self) -> DFA {
+        let live = self.live_states();
+        let reachable = self.reachable_states();
+        let mut keep: HashSet<String> = reachable
+            .iter()
+            .filter(|state| live.contains(*state))
+            .cloned()
+            .collect();
+        keep.insert(self.starting_state.clone());
+        let dead_state = self
+            .dead_state
+            .clone()
+            .filter(|dead| reachable.contains(dead));
+        keep.extend(dead_state.iter().cloned());
+
+        DFA {
+            name: self.name.clone(),
+            states: self
+                .states
+                .iter()
+                .filter(|state| keep.contains(*state))
+                .cloned()
+                .collect(),
+            alphabet: self.alphabet.clone(),
+            transition: self
+                .transition
+                .iter()
+                .filter(|((start, _), end)| keep.contains(start) && keep.contains(*end))
+                .map(|(key, end)| (key.clone(), end.clone()))
+                .collect(),
+            starting_state: self.starting_state.clone(),
+            accepting_states: self
+                .accepting_states
+                .iter()
+                .filter(|state| keep.contains(*state))
+                .cloned()
+                .collect(),
+            dead_state,
+            accepting: Default::default(),
+        }
+    }
+
+    /// Restricts the DFA to the symbols in `keep`. Transitions on any other
+    /// symbol are dropped, leaving those states partial, so words using a
+    /// removed symbol are rejected.
+    pub fn restrict_alphabet(&self, keep: &[String]) -> DFA {
+        DFA {
+            name: self.name.clone(),
+            states: self.states.clone(),
+            alphabet: self
+                .alphabet
+                .iter()
+                .filter(|symbol| keep.contains(symbol))
+                .cloned()
+                .collect(),
+            transition: self
+                .transition
+                .iter()
+                .filter(|((_, alphabet), _)| keep.contains(alphabet))
+                .map(|(key, end)| (key.clone(), end.clone()))
+                .collect(),
+            starting_state: self.starting_state.clone(),
+            accepting_states: self.accepting_states.clone(),
+            dead_state: self.dead_state.clone(),
+            accepting: Default::default(),
+        }
+    }
+
+    /// Non-accepting states that loop back to themselves on every symbol of
+    /// the alphabet, so any word reaching one is rejected.
+    pub fn trap_states(&self) -> Vec<String> {
+        self.states
+            .iter()
+            .filter(|state| !self.is_accepting(state))
+            .filter(|state| {
+                self.alphabet
+                    .iter()
+                    .all(|symbol| self.step(state, symbol) == Some(state.as_str()))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Adds a non-accepting state without transitions, named after the first
+    /// unused of `q0`, `q1`, ..., and returns that name.
+    pub fn add_state(&mut self) -> String {
+        let name = (0..)
+            .map(|i| format!("q{i}"))
+            .find(|name| !self.states.contains(name))
+            .unwrap();
+        self.states.push(name.clone());
+        name
+    }
+
+    /// Removes `state` along with every transition into or out of it.
+    pub fn remove_state(&mut self, state: &str) -> Result<(), String> {
+        if state == self.starting_state {
+            return Err(format!("Cannot remove starting state {state}."));
+        }
+        if !self.states.iter().any(|s| s == state) {
+            return Err(format!("{state} is not a valid State."));
+        }
+        self.states.retain(|s| s != state);
+        self.accepting_states.retain(|s| s != state);
+        self.accepting_changed();
+        self.transition
+            .retain(|(start, _), end| start != state && end != state);
+        if self.dead_state.as_deref() == Some(state) {
+            self.dead_state = None;
+        }
+        Ok(())
+    }
+
+    /// Sets the transition from `start` on `symbol` to `end`, replacing any
+    /// existing one. Both states and the symbol must already be declared.
+    pub fn set_transition(&mut self, start: &str, symbol: &str, end: &str) -> Result<(), String> {
+        for state in [start, end] {
+            if !self.states.iter().any(|s| s == state) {
+                return Err(format!("{state} is not a valid State."));
+            }
+        }
+        if !self.alphabet.iter().any(|s| s == symbol) {
+            return Err(format!("{symbol} is not in the alphabet."));
+        }
+        if self.dead_state.as_deref() == Some(start) && start != end {
+            return Err(format!(
+                "Dead State {start} cannot leave on {symbol} to {end}."
+            ));
+        }
+        self.transition
+            .insert((start.to_string(), symbol.to_string()), end.to_string());
+        Ok(())
+    }
+
+    /// Merges `b` into `a`: transitions into `b` go to `a` instead, `a` takes
+    /// over the transitions out of `b`, and `a` is starting or accepting if
+    /// either was. Fails if both had a transition on the same symbol to
+    /// different states, since `a` would then need to go to both.
+    pub fn merge_states(&self, a: &str, b: &str) -> Result<DFA, String> {
+        for state in [a, b] {
+            if !self.states.iter().any(|s| s == state) {
+                return Err(format!("{state} is not a valid State."));
+            }
+        }
+        if a == b {
+            return Ok(self.clone());
+        }
+        let rename = |state: &str| if state == b { a } else { state }.to_string();
+
+        let mut transition = BTreeMap::new();
+        for (start, symbol, end) in self.declared_order_transitions() {
+            let (start, end) = (rename(start), rename(end));
+            match transition.get(&(start.clone(), symbol.to_string())) {
+                Some(other) if *other != end => {
+                    return Err(format!(
+                        "Merging {b} into {a} makes {a},{symbol} go to both {other} and {end}."
+                    ))
+                }
+                _ => transition.insert((start, symbol.to_string()), end),
+            };
+        }
+        let mut accepting_states = vec![];
+        for state in self.accepting_states.iter().map(|state| rename(state)) {
+            if !accepting_states.contains(&state) {
+                accepting_states.push(state);
+            }
+        }
+        // A merged dead state stays declared only while it still rejects
+        // everything and never leaves.
+        let dead_state = self.dead_state.as_deref().map(rename).filter(|dead| {
+            !accepting_states.contains(dead)
+                && transition
+                    .iter()
+                    .all(|((start, _), end)| start != dead || end == dead)
+        });
+
+        Ok(DFA {
+            name: self.name.clone(),
+            states: self.states.iter().filter(|s| *s != b).cloned().collect(),
+            alphabet: self.alphabet.clone(),
+            transition,
+            starting_state: rename(&self.starting_state),
+            accepting_states,
+            dead_state,
+            accepting: Default::default(),
+        })
+    }
+
+    /// A DFA over `alphabet` accepting exactly the words whose length is a
+    /// multiple of `k`. State `qi` means `i` symbols modulo `k` were read.
+    pub fn modulo_length(alphabet: &[&str], k: usize) -> DFA {
+        assert!(k > 0, "Lengths cannot be taken modulo 0");
+        let names: Vec<String> = (0..k).map(|i| format!("q{i}")).collect();
+        DFA {
+            name: None,
+            states: names.clone(),
+            alphabet: alphabet.iter().map(|symbol| symbol.to_string()).collect(),
+            transition: names
+                .iter()
+                .enumerate()
+                .flat_map(|(i, state)| {
+                    let next = &names[(i + 1) % k];
+                    alphabet
+                        .iter()
+                        .map(move |symbol| ((state.clone(), symbol.to_string()), next.clone()))
+                })
+                .collect(),
+            starting_state: names[0].clone(),
+            accepting_states: vec![names[0].clone()],
+            dead_state: None,
+            accepting: Default::default(),
+        }
+    }
+
+    /// A DFA over `alphabet` accepting the words that contain `pattern` as a
+    /// contiguous substring, built from the KMP failure function. State `qi`
+    /// means the last `i` symbols read are the start of `pattern`, and
+    /// `q{pattern.len()}` is an accepting sink.
+    ///
+    /// # Panics
+    ///
+    /// If `pattern` holds a symbol that is not in `alphabet`.
+    pub fn contains_substring(alphabet: &[&str], pattern: &[&str]) -> DFA {
+        let symbol_index = |symbol: &str| {
+            alphabet
+                .iter()
+                .position(|s| *s == symbol)
+                .unwrap_or_else(|| panic!("{symbol} is not in the alphabet."))
+        };
+        let pattern: Vec<usize> = pattern.iter().map(|symbol| symbol_index(symbol)).collect();
+        let m = pattern.len();
+
+        // `delta[i][c]`: mismatches fall back to where the state `fallback`
+        // of the longest proper border would go.
+        let mut delta = vec![vec![0; alphabet.len()]; m + 1];
+        let mut fallback = 0;
+        for (i, &symbol) in pattern.iter().enumerate() {
+            delta[i] = delta[fallback].clone();
+            delta[i][symbol] = i + 1;
+            if i > 0 {
+                fallback = delta[fallback][symbol];
+            }
+        }
+        delta[m] = vec![m; alphabet.len()];
+
+        let names: Vec<String> = (0..=m).map(|i| format!("q{i}")).collect();
+        DFA {
+            name: None,
+            states: names.clone(),
+            alphabet: alphabet.iter().map(|symbol| symbol.to_string()).collect(),
+            transition: delta
+                .iter()
+                .enumerate()
+                .flat_map(|(from, row)| {
+                    let names = &names;
+                    row.iter().enumerate().map(move |(symbol, &to)| {
+                        (
+                            (names[from].clone(), alphabet[symbol].to_string()),
+                            names[to].clone(),
+                        )
+                    })
+                })
+                .collect(),
+            starting_state: names[0].clone(),
+            accepting_states: vec![names[m].clone()],
+            dead_state: None,
+            accepting: Default::default(),
+        }
+    }
+
+    /// Generates a total DFA with states `q0..q{states - 1}` over `alphabet`,
+    /// every state reachable from `q0`. The same seed always yields the same
+    /// machine, which makes it handy for benchmarks and property tests.
+    pub fn random(states: usize, alphabet: &[&str], seed: u64) -> DFA {
+        assert!(states > 0, "A DFA needs at least one state");
+        assert!(
+            states == 1 || !alphabet.is_empty(),
+            "Cannot connect {states} states without any symbols"
+        );
+        let mut rng = SplitMix64(seed);
+        let names: Vec<String> = (0..states).map(|i| format!("q{i}")).collect();
+        let mut delta: Vec<Vec<Option<usize>>> = vec![vec![None; alphabet.len()]; states];
+
+        // Spanning tree first: each new state hangs off a free slot of some
+        // earlier state, so everything is reachable from q0.
+        for state in 1..states {
+            let free: Vec<(usize, usize)> = (0..state)
+                .flat_map(|from| (0..alphabet.len()).map(move |symbol| (from, symbol)))
+                .filter(|&(from, symbol)| delta[from][symbol].is_none())
+                .collect();
+            let (from, symbol) = free[rng.below(free.len())];
+            delta[from][symbol] = Some(state);
+        }
+        for row in delta.iter_mut() {
+            for slot in row.iter_mut().filter(|slot| slot.is_none()) {
+                *slot = Some(rng.below(states));
+            }
+        }
+
+        DFA {
+            name: None,
+            states: names.clone(),
+            alphabet: alphabet.iter().map(|symbol| symbol.to_string()).collect(),
+            transition: delta
+                .iter()
+                .enumerate()
+                .flat_map(|(from, row)| {
+                    let names = &names;
+                    row.iter().enumerate().map(move |(symbol, to)| {
+                        (
+                            (names[from].clone(), alphabet[symbol].to_string()),
+                            names[to.unwrap()].clone(),
+                        )
+                    })
+                })
+                .collect(),
+            starting_state: names[0].clone(),
+            accepting_states: names
+                .iter()
+                .filter(|_| rng.below(2) == 0)
+                .cloned()
+                .collect(),
+            dead_state: None,
+            accepting: Default::default(),
+        }
+    }
+}
+
+// Small seeded generator for `DFA::random`; quality only needs to be good
+// enough that different seeds give visibly different machines.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+// Names the states of a construction after what they stand for. When two
+// keys would get the same name, the later one gets `_2`, `_3` and so on
+// appended, so distinct states never share a name.
+pub(crate) struct StateNames<K> {
+    names: HashMap<K, String>,
+    used: HashSet<String>,
+}
+
+impl<K: Hash + Eq + Clone> StateNames<K> {
+    pub(crate) fn new() -> StateNames<K> {
+        StateNames {
+            names: HashMap::new(),
+            used: HashSet::new(),
+        }
+    }
+
+    // The name given to `key`, built from `base` the first time it is asked.
+    pub(crate) fn get(&mut self, key: &K, base: impl FnOnce() -> String) -> String {
+        if let Some(name) = self.names.get(key) {
+            return name.clone();
+        }
+        let base = base();
+        let name = std::iter::once(base.clone())
+            .chain((2..).map(|i| format!("{base}_{i}")))
+            .find(|name| !self.used.contains(name))
+            .unwrap();
+        self.used.insert(name.clone());
+        self.names.insert(key.clone(), name.clone());
+        name
+    }
+}
+
+pub(crate) fn whitespace(code: &mut Peekable<CharIndices>) {
+    while code.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+}
+
+pub(crate) fn word(code: &mut Peekable<CharIndices>) -> String {
+    whitespace(code);
+    let word = std::iter::from_fn(|| {
+        code.by_ref()
+            .next_if(|(_, ch)| ch.is_alphanumeric() || *ch == '_')
+    })
+    .map(|(_, c)| c)
+    .collect();
+    whitespace(code);
+    word
+}
+
+// A word, or a range `x-y` of single characters expanded to `x`, ..., `y`.
+pub(crate) fn symbols(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
+    let start = word(code);
+    if code.next_if(|(_, ch)| *ch == '-').is_none() {
+        return Ok(vec![start]);
+    }
+    let end = word(code);
+
+    let (mut first, mut last) = (start.chars(), end.chars());
+    match (first.next(), first.next(), last.next(), last.next()) {
+        (Some(first), None, Some(last), None) if first <= last => {
+            Ok((first..=last).map(String::from).collect())
+        }
+        _ => Err(format!("Invalid Range {start}-{end}")),
+    }
+}
+
+pub(crate) fn list(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
+    whitespace(code);
+
+    match code.next() {
+        Some((_, '[')) => (),
+        Some((_, x)) => return Err(format!("Unexpected Symbol '{x}' Expected [")),
+        None => return Err("Unexpected End of File.".to_string()),
+    };
+    let mut list = vec![];
+    whitespace(code);
+    if code.next_if(|(_, ch)| *ch == ']').is_some() {
+        return Ok(list);
+    }
+    loop {
+        list.extend(symbols(code)?);
+        match code.next() {
+            Some((_, ',')) => (),
+            Some((_, ']')) => break,
+            Some((_, x)) => return Err(format!("Unexpected Symbol '{x}' Expected ,")),
+            None => return Err("Unexpected End of File.".to_string()),
+        }
+    }
+    Ok(list)
+}
+
+pub(crate) fn keyword(code: &mut Peekable<CharIndices>, keyword: &str) -> Result<bool, String> {
+    match word(code) {
+        x if x == keyword => return Ok(true),
+        x => Err(format!("Expected {keyword}")),
+    }
+}
+
+pub(crate) fn char(code: &mut Peekable<CharIndices>, ch: char) -> Result<bool, String> {
+    whitespace(code);
+    let result = match code.next() {
+        Some((_, x)) if x == ch => Ok(true),
+        Some((_, x)) => Err(format!("Unexpected Symbol '{x}' Expected {ch}")),
+        None => Err("Unexpected End of file.".to_string()),
+    };
+    whitespace(code);
+    return result;
+}
+
+fn states(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
+    keyword(code, "states")?;
+    char(code, '=')?;
+    list(code)
+}
+
+fn alphabet(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
+    keyword(code, "alphabet")?;
+    char(code, '=')?;
+    list(code)
+}
+
+fn starting_state(code: &mut Peekable<CharIndices>) -> Result<String, String> {
+    keyword(code, "starting_state")?;
+    char(code, '=')?;
+    Ok(word(code))
+}
+
+fn accepting_states(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
+    keyword(code, "accepting_states")?;
+    char(code, '=')?;
+    list(code)
+}
+
+// A quoted string on one line, e.g. `name = "My DFA"`, optionally followed
+// by `;`. There are no escapes, so the name cannot contain `"`.
+fn name(code: &mut Peekable<CharIndices>) -> Result<String, String> {
+    keyword(code, "name")?;
+    char(code, '=')?;
+    match code.next() {
+        Some((_, '"')) => (),
+        Some((_, x)) => return Err(format!("Unexpected Symbol '{x}' Expected \"")),
+        None => return Err("Unexpected End of File.".to_string()),
+    }
+    let name = std::iter::from_fn(|| code.next_if(|(_, ch)| *ch != '"' && *ch != '\n'))
+        .map(|(_, ch)| ch)
+        .collect();
+    match code.next() {
+        Some((_, '"')) => (),
+        Some(_) => return Err("Unterminated name".to_string()),
+        None => return Err("Unexpected End of File.".to_string()),
+    }
+    whitespace(code);
+    code.next_if(|(_, ch)| *ch == ';');
+    Ok(name)
+}
+
+// The state declared as the sink, e.g. `dead_state = q_dead;`, with the
+// `;` optional.
+fn dead_state(code: &mut Peekable<CharIndices>) -> Result<String, String> {
+    keyword(code, "dead_state")?;
+    char(code, '=')?;
+    let state = word(code);
+    if state.is_empty() {
+        return match code.peek() {
+            Some((_, x)) => Err(format!("Unexpected Symbol '{x}' Expected a state")),
+            None => Err("Unexpected End of File.".to_string()),
+        };
+    }
+    whitespace(code);
+    code.next_if(|(_, ch)| *ch == ';');
+    Ok(state)
+}
+
+// A state name, recording it in `accepting` if written with a `*` prefix.
+pub(crate) fn marked_state(
+    code: &mut Peekable<CharIndices>,
+    accepting: &mut Vec<String>,
+) -> String {
+    whitespace(code);
+    let marked = code.next_if(|(_, ch)| *ch == '*').is_some();
+    let state = word(code);
+    if marked && !accepting.contains(&state) {
+        accepting.push(state.clone());
+    }
+    state
+}
+
+// Transitions of a DFA as parsed, keyed by `(state, symbol)`.
+type Transitions = BTreeMap<(String, String), String>;
+
+// Every target given to each `(state, symbol)` in the `transitions` section,
+// in the order written, with the byte offset of the rule giving it.
+type Rules = BTreeMap<(String, String), Vec<(String, usize)>>;
+
+// Returns the transitions along with the states marked accepting by `*`,
+// keeping the first target of a `(state, symbol)` given several.
+#[cfg(test)]
+fn transitions(code: &mut Peekable<CharIndices>) -> Result<(Transitions, Vec<String>), String> {
+    let mut errors = vec![];
+    let (rules, accepting) = transitions_recovering(code, &mut errors)?;
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok((
+            rules
+                .into_iter()
+                .map(|(key, mut targets)| (key, targets.swap_remove(0).0))
+                .collect(),
+            accepting,
+        )),
+    }
+}
+
+// Like `transitions`, but adds each malformed rule to `errors` and skips it,
+// and keeps every target along with where its rule starts.
+fn transitions_recovering(
+    code: &mut Peekable<CharIndices>,
+    errors: &mut Vec<String>,
+) -> Result<(Rules, Vec<String>), String> {
+    keyword(code, "transitions")?;
+    char(code, '=')?;
+
+    let mut transitions = Rules::new();
+    let mut accepting = vec![];
+    while code.peek().is_some() && peek_section(code).is_none() {
+        let offset = code.peek().map_or(0, |&(offset, _)| offset);
+        match transition(code, &mut accepting) {
+            Ok(Some((start_state, inputs, final_state))) => {
+                for input in inputs {
+                    transitions
+                        .entry((start_state.clone(), input))
+                        .or_default()
+                        .push((final_state.clone(), offset));
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                errors.push(err);
+                skip_rule(code);
+            }
+        }
+        whitespace(code);
+    }
+    Ok((transitions, accepting))
+}
+
+// A single rule `start,symbols = end;`, or `None` at a `]` closing the
+// section.
+fn transition(
+    code: &mut Peekable<CharIndices>,
+    accepting: &mut Vec<String>,
+) -> Result<Option<(String, Vec<String>, String)>, String> {
+    let start_state = marked_state(code, accepting);
+    if start_state.is_empty() {
+        return match code.peek() {
+            Some((_, x)) => Err(format!("Unexpected Symbol '{x}' Expected a state")),
+            None => Err("Unexpected End of File.".to_string()),
+        };
+    }
+    match code.next() {
+        Some((_, ',')) => (),
+        Some((_, ']')) => return Ok(None),
+        Some((_, x)) => return Err(format!("Unexpected Symbol '{x}' Expected ',' or ']'")),
+        None => return Err("Unexpected End of File.".to_string()),
+    }
+    let inputs = symbols(code)?;
+    char(code, '=')?;
+
+    let final_state = marked_state(code, accepting);
+    char(code, ';')?;
+    Ok(Some((start_state, inputs, final_state)))
+}
+
+// Hopcroft's algorithm over a total transition table `delta[symbol][state]`.
+// Returns the block of the coarsest stable partition each state belongs to.
+fn hopcroft(delta: &[Vec<usize>], accepting: &[bool]) -> Vec<usize> {
+    let n = accepting.len();
+    let mut inverse = vec![vec![vec![]; n]; delta.len()];
+    for (c, row) in delta.iter().enumerate() {
+        for (q, &end) in row.iter().enumerate() {
+            inverse[c][end].push(q);
+        }
+    }
+
+    let (accept, reject): (Vec<usize>, Vec<usize>) = (0..n).partition(|&q| accepting[q]);
+    let mut partition: Vec<Vec<usize>> = [accept, reject]
+        .into_iter()
+        .filter(|block| !block.is_empty())
+        .collect();
+    let mut block_of = vec![0; n];
+    for (b, block) in partition.iter().enumerate() {
+        for &q in block {
+            block_of[q] = b;
+        }
+    }
+
+    let mut work: Vec<usize> = (0..partition.len()).collect();
+    while let Some(splitter) = work.pop() {
+        let splitter = partition[splitter].clone();
+        for inverse in &inverse {
+            let mut touched: HashMap<usize, Vec<usize>> = HashMap::new();
+            let predecessors: HashSet<usize> = splitter
+                .iter()
+                .flat_map(|&q| inverse[q].iter().copied())
+                .collect();
+            for &p in &predecessors {
+                touched.entry(block_of[p]).or_default().push(p);
+            }
+
+            for (b, inside) in touched {
+                if inside.len() == partition[b].len() {
+                    continue;
+                }
+                partition[b].retain(|q| !predecessors.contains(q));
+                let new = partition.len();
+                for &q in &inside {
+                    block_of[q] = new;
+                }
+                partition.push(inside);
+
+                if work.contains(&b) || partition[new].len() <= partition[b].len() {
+                    work.push(new);
+                } else {
+                    work.push(b);
+                }
+            }
+        }
+    }
+    block_of
+}
+
+// Moore's algorithm over a total transition table `delta[symbol][state]`.
+// Each round numbers states by their block and the blocks of their
+// successors, and stops once that no longer adds blocks.
+fn moore(delta: &[Vec<usize>], accepting: &[bool]) -> Vec<usize> {
+    let mut block_of: Vec<usize> = accepting.iter().map(|&accept| accept as usize).collect();
+    let mut count = block_of.iter().collect::<HashSet<_>>().len();
+    loop {
+        let mut blocks: HashMap<Vec<usize>, usize> = HashMap::new();
+        let next: Vec<usize> = (0..accepting.len())
+            .map(|q| {
+                let signature = std::iter::once(block_of[q])
+                    .chain(delta.iter().map(|row| block_of[row[q]]))
+                    .collect();
+                let new = blocks.len();
+                *blocks.entry(signature).or_insert(new)
+            })
+            .collect();
+        if blocks.len() == count {
+            return next;
+        }
+        count = blocks.len();
+        block_of = next;
+    }
+}
+
+// Name of the section starting at `code`, without consuming it.
+pub(crate) fn peek_section(code: &Peekable<CharIndices>) -> Option<String> {
+    let mut ahead = code.clone();
+    let name = word(&mut ahead);
+    match ahead.peek() {
+        Some((_, '=')) if !name.is_empty() => Some(name),
+        _ => None,
+    }
+}
+
+fn fill<T>(slot: &mut Option<T>, section: &str, value: T, errors: &mut Vec<String>) {
+    match slot {
+        Some(_) => errors.push(format!("Duplicate section {section}")),
+        None => *slot = Some(value),
+    }
+}
+
+fn require<T>(slot: Option<T>, section: &str, errors: &mut Vec<String>) -> Option<T> {
+    if slot.is_none() {
+        errors.push(format!("Missing section {section}"));
+    }
+    slot
+}
+
+// The sections of a machine description. `transitions` is whatever the
+// caller's transition parser produces, so the NFA format can share the rest.
+pub(crate) struct Sections<T> {
+    pub name: Option<String>,
+    pub states: Vec<String>,
+    pub alphabet: Vec<String>,
+    pub starting_state: String,
+    pub accepting_states: Vec<String>,
+    pub dead_state: Option<String>,
+    pub transitions: T,
+}
+
+// Type of a parser for the `transitions` section, returning the transitions
+// along with the states marked accepting by `*`. A malformed rule is added to
+// the errors and skipped, while an error in the section header is returned.
+pub(crate) type TransitionsParser<T> =
+    fn(&mut Peekable<CharIndices>, &mut Vec<String>) -> Result<(T, Vec<String>), String>;
+
+// Parses the sections in whatever order they appear, requiring each exactly
+// once, and fails with the first error found.
+pub(crate) fn sections<T>(
+    code: &mut Peekable<CharIndices>,
+    transitions: TransitionsParser<T>,
+) -> Result<Sections<T>, String> {
+    let mut errors = vec![];
+    let parsed = sections_recovering(code, transitions, &mut errors);
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(parsed.expect("sections are only missing with an error")),
+    }
+}
+
+// Like `sections`, but adds every error to `errors` and carries on from the
+// next section, so one pass finds as many as possible. Returns `None` if a
+// required section is missing or could not be parsed.
+pub(crate) fn sections_recovering<T>(
+    code: &mut Peekable<CharIndices>,
+    transitions: TransitionsParser<T>,
+    errors: &mut Vec<String>,
+) -> Option<Sections<T>> {
+    let (mut parsed_states, mut parsed_alphabet, mut parsed_starting_state) = (None, None, None);
+    let (mut parsed_accepting_states, mut parsed_transitions) = (None, None);
+    let (mut parsed_name, mut parsed_dead_state) = (None, None);
+
+    whitespace(code);
+    while let Some(&(_, ch)) = code.peek() {
+        let Some(section) = peek_section(code) else {
+            errors.push(format!("Unexpected Symbol '{ch}' Expected a section"));
+            skip_to_section(code);
+            continue;
+        };
+        let parsed = match section.as_str() {
+            "name" => name(code).map(|value| fill(&mut parsed_name, &section, value, errors)),
+            "states" => states(code).map(|value| fill(&mut parsed_states, &section, value, errors)),
+            "alphabet" => {
+                alphabet(code).map(|value| fill(&mut parsed_alphabet, &section, value, errors))
+            }
+            "starting_state" => starting_state(code)
+                .map(|value| fill(&mut parsed_starting_state, &section, value, errors)),
+            "accepting_states" => accepting_states(code)
+                .map(|value| fill(&mut parsed_accepting_states, &section, value, errors)),
+            "dead_state" => {
+                dead_state(code).map(|value| fill(&mut parsed_dead_state, &section, value, errors))
+            }
+            "transitions" => transitions(code, errors)
+                .map(|value| fill(&mut parsed_transitions, &section, value, errors)),
+            _ => {
+                // Still at the start of the unknown section, so move past it.
+                code.find(|(_, ch)| *ch == '\n');
+                Err(format!("Unknown section {section}"))
+            }
+        };
+        if let Err(err) = parsed {
+            errors.push(err);
+            skip_to_section(code);
+        }
+        whitespace(code);
+    }
+
+    let states = require(parsed_states, "states", errors);
+    let alphabet = require(parsed_alphabet, "alphabet", errors);
+    let starting_state = require(parsed_starting_state, "starting_state", errors);
+    let accepting_states = require(parsed_accepting_states, "accepting_states", errors);
+    let transitions = require(parsed_transitions, "transitions", errors);
+    let (states, alphabet, starting_state, mut accepting_states, (transitions, marked)) = (
+        states?,
+        alphabet?,
+        starting_state?,
+        accepting_states?,
+        transitions?,
+    );
+    for state in marked {
+        if !accepting_states.contains(&state) {
+            accepting_states.push(state);
+        }
+    }
+    Some(Sections {
+        name: parsed_name,
+        states,
+        alphabet,
+        starting_state,
+        accepting_states,
+        dead_state: parsed_dead_state,
+        transitions,
+    })
+}
+
+// Skips ahead to the next line that starts a section, or to the end.
+fn skip_to_section(code: &mut Peekable<CharIndices>) {
+    while code.peek().is_some() && peek_section(code).is_none() {
+        code.find(|(_, ch)| *ch == '\n');
+        whitespace(code);
+    }
+}
+
+// Skips the rest of a malformed transition rule: up to and including the next
+// `;`, or to the next section if the rule is never terminated.
+pub(crate) fn skip_rule(code: &mut Peekable<CharIndices>) {
+    while let Some((_, ch)) = code.next() {
+        if ch == ';' {
+            break;
+        }
+        if ch == '\n' {
+            whitespace(code);
+            if peek_section(code).is_some() {
+                break;
+            }
+        }
+    }
+}
+
+// Replaces every `state,_ = target` rule with a transition to `target` on each
+// symbol that `state` has no explicit transition for.
+fn expand_wildcards(transitions: &mut BTreeMap<(String, String), String>, alphabet: &[String]) {
+    let wildcards: Vec<_> = transitions
+        .keys()
+        .filter(|(_, symbol)| symbol == "_")
+        .cloned()
+        .collect();
+    for key in wildcards {
+        let target = transitions.remove(&key).unwrap();
+        for symbol in alphabet {
+            transitions
+                .entry((key.0.clone(), symbol.clone()))
+                .or_insert_with(|| target.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod states_tests {
+    use super::*;
+
+    #[test]
+    fn missing_states_keyword() {
+        assert_eq!(
+            states(&mut "state = [q1, q2,q3, q4, q5]".char_indices().peekable()),
+            Err("Expected states".to_string()),
+        );
+    }
+
+    #[test]
+    fn missing_equal_symbol() {
+        assert_eq!(
+            states(&mut "states , [q1, q2,q3, q4, q5]".char_indices().peekable()),
+            Err("Unexpected Symbol ',' Expected =".to_string()),
+        );
+    }
+
+    #[test]
+    fn valid_parse() {
+        assert_eq!(
+            states(&mut "states = [q1, q2,q3, q4, q5]".char_indices().peekable()).unwrap(),
+            vec!["q1", "q2", "q3", "q4", "q5"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod list_tests {
+    use super::*;
+
+    #[test]
+    fn empty_list() {
+        assert_eq!(list(&mut "[ ]".char_indices().peekable()), Ok(vec![]));
+        assert_eq!(list(&mut "[]".char_indices().peekable()), Ok(vec![]));
+    }
+}
+
+#[cfg(test)]
+mod alphabet_tests {
+    use super::*;
+
+    #[test]
+    fn missing_states_keyword() {
+        assert_eq!(
+            alphabet(&mut "alphabett = [a,b]".char_indices().peekable()),
+            Err("Expected alphabet".to_string()),
+        );
+    }
+
+    #[test]
+    fn missing_equal_symbol() {
+        assert_eq!(
+            alphabet(&mut "alphabet , [a,b]".char_indices().peekable()),
+            Err("Unexpected Symbol ',' Expected =".to_string()),
+        );
+    }
+
+    #[test]
+    fn valid_parse() {
+        assert_eq!(
+            alphabet(&mut "alphabet = [a,b]".char_indices().peekable()).unwrap(),
+            vec!["a", "b"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod starting_state_tests {
+    use super::*;
+
+    #[test]
+    fn missing_states_keyword() {
+        assert_eq!(
+            starting_state(&mut "starting_statea = q1".char_indices().peekable()),
+            Err("Expected starting_state".to_string()),
+        );
+    }
+
+    #[test]
+    fn missing_equal_symbol() {
+        assert_eq!(
+            starting_state(&mut "starting_state , q1".char_indices().peekable()),
+            Err("Unexpected Symbol ',' Expected =".to_string()),
+        );
+    }
+
+    #[test]
+    fn valid_parse() {
+        assert_eq!(
+            starting_state(&mut "starting_state = q1".char_indices().peekable()).unwrap(),
+            "q1"
+        );
+    }
+}
+
+#[cfg(test)]
+mod accepting_states_tests {
+    use super::*;
+
+    #[test]
+    fn missing_states_keyword() {
+        assert_eq!(
+            accepting_states(
+                &mut "accepting_statets = [q1, q2,q3, q4, q5]"
+                    .char_indices()
+                    .peekable()
+            ),
+            Err("Expected accepting_states".to_string()),
+        );
+    }
+
+    #[test]
+    fn missing_equal_symbol() {
+        assert_eq!(
+            accepting_states(
+                &mut "accepting_states , [q1, q2,q3, q4, q5]"
+                    .char_indices()
+                    .peekable()
+            ),
+            Err("Unexpected Symbol ',' Expected =".to_string()),
+        );
+    }
+
+    #[test]
+    fn valid_parse() {
+        assert_eq!(
+            accepting_states(
+                &mut "accepting_states = [q1, q2,q3, q4, q5]"
+                    .char_indices()
+                    .peekable()
+            )
+            .unwrap(),
+            vec!["q1", "q2", "q3", "q4", "q5"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod range_tests {
+    use super::*;
+
+    #[test]
+    fn alphabet_range() {
+        assert_eq!(
+            alphabet(&mut "alphabet = [a-c, x]".char_indices().peekable()).unwrap(),
+            vec!["a", "b", "c", "x"]
+        );
+    }
+
+    #[test]
+    fn invalid_range() {
+        assert_eq!(
+            alphabet(&mut "alphabet = [c-a]".char_indices().peekable()),
+            Err("Invalid Range c-a".to_string())
+        );
+        assert_eq!(
+            alphabet(&mut "alphabet = [ab-c]".char_indices().peekable()),
+            Err("Invalid Range ab-c".to_string())
+        );
+    }
+
+    #[test]
+    fn transition_range() {
+        let tran = transitions(&mut "transitions =\n    q1,a-c = q2;".char_indices().peekable())
+            .unwrap()
+            .0;
+        assert_eq!(tran.len(), 3);
+        for symbol in ["a", "b", "c"] {
+            assert_eq!(tran[&("q1".to_string(), symbol.to_string())], "q2");
+        }
+    }
+}
+
+#[cfg(test)]
+mod transitions_tests {
+    use super::*;
+
+    #[test]
+    fn valid_parse() {
+        let mut tran: BTreeMap<(String, String), String> = BTreeMap::new();
+        tran.insert(("q1".to_string(), "a".to_string()), "q2".to_string());
+        tran.insert(("q1".to_string(), "b".to_string()), "q1".to_string());
+        tran.insert(("q2".to_string(), "a".to_string()), "q1".to_string());
+        tran.insert(("q2".to_string(), "b".to_string()), "q2".to_string());
+        assert_eq!(
+            transitions(
+                &mut r#"
+transitions =
+    q1,a = q2;
+    q1,b = q1;
+    q2,a = q1;
+    q2,b = q2;
+"#
+                .char_indices()
+                .peekable()
+            )
+            .unwrap(),
+            (tran, vec![])
+        );
+    }
+
+    #[test]
+    fn marked_accepting_states() {
+        let (tran, accepting) = transitions(
+            &mut "transitions =\n    *q1,a = *q2;\n    q2,a = *q2;"
+                .char_indices()
+                .peekable(),
+        )
+        .unwrap();
+        assert_eq!(tran[&("q1".to_string(), "a".to_string())], "q2");
+        assert_eq!(accepting, vec!["q1", "q2"]);
+    }
+
+    #[test]
+    fn marked_states_merge_with_list() {
+        let dfa = DFA::try_from(
+            "states = [q1, q2, q3]
+alphabet = [a]
+starting_state = q1
+accepting_states = [q3]
+transitions =
+    q1,a = *q2;
+    q2,a = q3;
+    q3,a = q3;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(dfa.accepting_states, vec!["q3", "q2"]);
+    }
+
+    const CONFLICTING: &str = "states = [q1, q2, q3]
+alphabet = [a, b]
+starting_state = q1
+accepting_states = [q3]
+transitions =
+    q1,a = q2;
+    q1,b = q1;
+    q2,a-b = q3;
+    q1,a-b = q3;
+    q2,a = q3;";
+
+    #[test]
+    fn conflicting_rules() {
+        assert_eq!(
+            DFA::try_from(CONFLICTING),
+            Err("State q1 on 'a' goes to both q2 (line 6) and q3 (line 9)".to_string())
+        );
+        // `q1,b` conflicts too, while repeating `q2,a = q3` is harmless.
+        assert_eq!(
+            DFA::parse_collect(CONFLICTING),
+            Err(vec![
+                "State q1 on 'a' goes to both q2 (line 6) and q3 (line 9)".to_string(),
+                "State q1 on 'b' goes to both q1 (line 7) and q3 (line 9)".to_string(),
+            ])
+        );
+    }
+}
+
+#[cfg(test)]
+mod dfa_io_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn io_test() {
+        let code = fs::read_to_string("./test.dfa").unwrap();
+        let dfa = DFA::try_from(code.clone());
+        dfa.expect("Error parsing dfa");
+    }
+
+    #[test]
+    fn parse() {
+        let code = fs::read_to_string("./test.dfa").unwrap();
+        let dfa: DFA = code.parse().unwrap();
+        assert_eq!(Ok(dfa), DFA::try_from(code));
+        assert!("states = [q0]".parse::<DFA>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod display_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let code: String = (&dfa).into();
+        assert_eq!(DFA::try_from(code).unwrap(), dfa);
+    }
+
+    #[test]
+    fn independent_of_rule_order() {
+        // The same rules written in two orders serialize the same way.
+        let header = "states = [q0, q1]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =";
+        let rules = [
+            "    q0,a = q1;",
+            "    q0,b = q0;",
+            "    q1,a = q0;",
+            "    q1,b = q1;",
+        ];
+        let forwards = format!("{header}\n{}", rules.join("\n"));
+        let backwards: Vec<_> = rules.iter().rev().copied().collect();
+        let backwards = format!("{header}\n{}", backwards.join("\n"));
+        assert_eq!(
+            String::from(&DFA::try_from(forwards).unwrap()),
+            String::from(&DFA::try_from(backwards).unwrap())
+        );
+    }
+
+    #[test]
+    fn declared_order() {
+        let dfa = DFA::try_from(
+            "states = [q1, q0]
+alphabet = [b, a]
+starting_state = q1
+accepting_states = [q0]
+transitions =
+    q0,a = q1;
+    q1,a = q0;
+    q0,b = q0;
+    q1,b = q1;"
+                .to_string(),
+        )
+        .unwrap();
+        assert!(dfa.to_string().ends_with(
+            "transitions =
+    q1,b = q1;
+    q1,a = q0;
+    q0,b = q0;
+    q0,a = q1;"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod pumping_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn pumping_length_is_state_count() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.pumping_length(), 2);
+    }
+
+    #[test]
+    fn short_word_has_no_decomposition() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.decompose_pump(&["a"]), None);
+    }
+
+    #[test]
+    fn decomposition_pumps() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let word = ["a", "b", "a"];
+        let (x, y, z) = dfa.decompose_pump(&word).unwrap();
+
+        assert!(!y.is_empty());
+        assert!(x.len() + y.len() <= dfa.pumping_length());
+        assert_eq!([x.clone(), y.clone(), z.clone()].concat(), word);
+        for i in 0..4 {
+            let pumped = [x.clone(), vec![y.clone(); i].concat(), z.clone()].concat();
+            let pumped = pumped.iter().map(String::as_str).collect::<Vec<_>>();
+            assert!(dfa.accepts(&pumped));
+        }
+    }
+}
+
+#[cfg(test)]
+mod concat_tests {
+    use super::*;
+
+    // The partial DFA accepting just `symbol`, shared with `rename_tests`.
+    pub(super) fn single(symbol: &str) -> DFA {
+        DFA::try_from(format!(
+            "states = [q0, q1]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,{symbol} = q1;"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn accepts_exactly_ab() {
+        let dfa = single("a").concat(&single("b")).unwrap().to_dfa();
+        assert!(dfa.accepts(&["a", "b"]));
+        for word in [
+            vec![],
+            vec!["a"],
+            vec!["b"],
+            vec!["b", "a"],
+            vec!["a", "b", "b"],
+        ] {
+            assert!(!dfa.accepts(&word), "{word:?}");
+        }
+    }
+
+    #[test]
+    fn mismatched_alphabets() {
+        let mut other = single("b");
+        other.alphabet = vec!["b".to_string()];
+        assert_eq!(
+            single("a").concat(&other),
+            Err("Alphabets [a, b] and [b] do not match.".to_string())
+        );
+    }
+}
+
+#[cfg(test)]
+mod product_tests {
+    use super::*;
+
+    // Words with an even number of `a`s.
+    fn even_a() -> DFA {
+        DFA::try_from(
+            "states = [even, odd]
+alphabet = [a, b]
+starting_state = even
+accepting_states = [even]
+transitions =
+    even,a = odd;
+    even,b = even;
+    odd,a = even;
+    odd,b = odd;"
+                .to_string(),
+        )
+        .unwrap()
+    }
+
+    // Words `a...ab`, without a transition out of the final state.
+    fn a_star_b() -> DFA {
+        DFA::try_from(
+            "states = [q0, q1]
+alphabet = [b, a]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q0;
+    q0,b = q1;"
+                .to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn check_reports_mismatch() {
+        assert_eq!(even_a().check(&[&[], &["a", "a"]], &[&["a"]]), Ok(()));
+        assert_eq!(
+            even_a().check(&[&["a"]], &[]),
+            Err("Expected [\"a\"] to be accepted.".to_string())
+        );
+        assert_eq!(
+            even_a().check(&[], &[&["b"]]),
+            Err("Expected [\"b\"] to be rejected.".to_string())
+        );
+    }
+
+    #[test]
+    fn union() {
+        let dfa = even_a().union(&a_star_b()).unwrap();
+        dfa.check(
+            &[&[], &["a", "a"], &["b"], &["a", "b"], &["a", "a", "b"]],
+            &[&["a"], &["a", "a", "a"], &["b", "a"]],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn intersect() {
+        let dfa = even_a().intersect(&a_star_b()).unwrap();
+        dfa.check(
+            &[&["b"], &["a", "a", "b"]],
+            &[
+                &[],
+                &["a", "b"],
+                &["a", "a"],
+                &["b", "a"],
+                &["a", "b", "a", "b"],
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn generated_names_parse_back() {
+        let both = even_a().intersect(&a_star_b()).unwrap();
+        assert_eq!(both.starting_state, "even_q0");
+        let mut b_only = a_star_b();
+        b_only.transition.clear();
+        b_only.set_transition("q0", "b", "q1").unwrap();
+        let either = even_a().union(&b_only).unwrap();
+        assert!(either.states.contains(&"odd_".to_string()));
+        let concat = even_a().concat(&a_star_b()).unwrap().to_dfa();
+        let star = a_star_b().star().to_dfa();
+        for dfa in [both, either, concat, star] {
+            assert_eq!(DFA::try_from(String::from(&dfa)), Ok(dfa.clone()), "{dfa}");
+        }
+    }
+
+    #[test]
+    fn generated_names_do_not_collide() {
+        // `(a_b, c)` and `(a, b_c)` would both be named `a_b_c`.
+        let left = DFA::try_from(
+            "states = [a_b, a]
+alphabet = [x]
+starting_state = a_b
+accepting_states = [a_b]
+transitions =
+    a_b,x = a;
+    a,x = a;",
+        )
+        .unwrap();
+        let right = DFA::try_from(
+            "states = [c, b_c]
+alphabet = [x]
+starting_state = c
+accepting_states = [c]
+transitions =
+    c,x = b_c;
+    b_c,x = b_c;",
+        )
+        .unwrap();
+        let both = left.intersect(&right).unwrap();
+        assert_eq!(both.states, vec!["a_b_c", "a_b_c_2"]);
+        both.check(&[&[]], &[&["x"], &["x", "x"]]).unwrap();
+        assert_eq!(DFA::try_from(String::from(&both)), Ok(both));
+    }
+
+    #[test]
+    fn intersects() {
+        assert!(even_a().intersects(&a_star_b()));
+        // Words without any `b` share nothing with `a*b`.
+        let only_a = DFA::try_from(
+            "states = [q0]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q0]
+transitions =
+    q0,a = q0;"
+                .to_string(),
+        )
+        .unwrap();
+        assert!(only_a.intersects(&even_a()));
+        assert!(!only_a.intersects(&a_star_b()));
+    }
+
+    #[test]
+    fn equivalence_witness() {
+        let dfa = even_a();
+        assert_eq!(dfa.equivalence_witness(&dfa.minimize()), None);
+
+        let witness = dfa.equivalence_witness(&a_star_b()).unwrap();
+        let word: Vec<&str> = witness.iter().map(String::as_str).collect();
+        assert_eq!(word, Vec::<&str>::new());
+        assert_ne!(dfa.accepts(&word), a_star_b().accepts(&word));
+
+        let both = dfa.intersect(&a_star_b()).unwrap();
+        let witness = a_star_b().equivalence_witness(&both).unwrap();
+        let word: Vec<&str> = witness.iter().map(String::as_str).collect();
+        assert_eq!(word, vec!["a", "b"]);
+        assert_ne!(both.accepts(&word), a_star_b().accepts(&word));
+    }
+
+    #[test]
+    fn product_bfs_witness() {
+        let word = |witness: Option<Vec<String>>| witness.map(|word| word.join(""));
+        // `even_a` accepts the empty word and `a*b` does not; `ab` is in `a*b`
+        // with an odd number of `a`s.
+        let difference = |left: bool, right: bool| left && !right;
+        assert_eq!(
+            word(even_a().product_bfs_witness(&a_star_b(), difference)),
+            Some(String::new())
+        );
+        assert_eq!(
+            word(a_star_b().product_bfs_witness(&even_a(), difference)),
+            Some("ab".to_string())
+        );
+        assert_eq!(
+            a_star_b().product_bfs_witness(&a_star_b().minimize(), difference),
+            None
+        );
+
+        let symmetric = |left: bool, right: bool| left != right;
+        for (left, right) in [(even_a(), a_star_b()), (a_star_b(), even_a())] {
+            assert_eq!(
+                left.product_bfs_witness(&right, symmetric),
+                left.equivalence_witness(&right)
+            );
+        }
+        let both = even_a().intersect(&a_star_b()).unwrap();
+        assert_eq!(
+            word(a_star_b().product_bfs_witness(&both, symmetric)),
+            Some("ab".to_string())
+        );
+    }
+
+    #[test]
+    fn product_bfs_witness_off_both() {
+        // Both accept exactly the words without a `b`, and neither has a
+        // transition on `b`.
+        let no_b = DFA::try_from(
+            "states = [q0]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q0]
+transitions =
+    q0,a = q0;",
+        )
+        .unwrap();
+        let neither = |left: bool, right: bool| !left && !right;
+        assert_eq!(
+            no_b.product_bfs_witness(&no_b, neither),
+            Some(vec!["b".to_string()])
+        );
+    }
+
+    #[test]
+    fn equivalence_witness_different_alphabets() {
+        let mut other = a_star_b();
+        other.alphabet.push("c".to_string());
+        assert_eq!(a_star_b().equivalence_witness(&other), None);
+        other.set_transition("q1", "c", "q1").unwrap();
+        assert_eq!(
+            a_star_b().equivalence_witness(&other),
+            Some(vec!["b".to_string(), "c".to_string()])
+        );
+    }
+
+    #[test]
+    fn mismatched_alphabets() {
+        let mut other = a_star_b();
+        other.alphabet = vec!["b".to_string()];
+        assert!(even_a().union(&other).is_err());
+        assert!(even_a().intersect(&other).is_err());
+    }
+
+    #[test]
+    fn intersects_different_alphabets() {
+        // Only `b` is left, which `even_a` accepts too.
+        let mut other = a_star_b();
+        other.alphabet = vec!["b".to_string()];
+        other.transition.retain(|(_, symbol), _| symbol == "b");
+        assert!(even_a().intersects(&other));
+        other.accepting_states = vec!["q0".to_string()];
+        other.accepting_changed();
+        assert!(even_a().intersects(&other));
+        other.accepting_states.clear();
+        other.accepting_changed();
+        assert!(!even_a().intersects(&other));
+    }
+}
+
+#[cfg(test)]
+mod star_tests {
+    use super::*;
+
+    #[test]
+    fn star_of_ab() {
+        let ab = DFA::try_from(
+            "states = [q0, q1, q2]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q2]
+transitions =
+    q0,a = q1;
+    q1,b = q2;"
+                .to_string(),
+        )
+        .unwrap();
+        let nfa = ab.star();
+        let dfa = nfa.to_dfa();
+
+        for word in [vec![], vec!["a", "b"], vec!["a", "b", "a", "b"]] {
+            assert!(nfa.accepts(&word), "{word:?}");
+            assert!(dfa.accepts(&word), "{word:?}");
+        }
+        for word in [vec!["a"], vec!["b"], vec!["a", "b", "a"]] {
+            assert!(!nfa.accepts(&word), "{word:?}");
+            assert!(!dfa.accepts(&word), "{word:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod rename_tests {
+    use super::concat_tests::single;
+    use super::*;
+
+    #[test]
+    fn sequential_names() {
+        let dfa = single("a").concat(&single("b")).unwrap().to_dfa();
+        assert_eq!(dfa.starting_state, "1_q0");
+
+        assert_eq!(
+            dfa.rename_states_canonical(),
+            DFA::try_from(
+                "states = [q0, q1, q2]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q2]
+transitions =
+    q0,a = q1;
+    q1,b = q2;"
+                    .to_string()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn drops_unreachable_states() {
+        let dfa = DFA::try_from(std::fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.rename_states_canonical().states, vec!["q0"]);
+    }
+}
+
+#[cfg(test)]
+mod trim_tests {
+    use super::*;
+
+    // q2 is a dead branch and q3 is unreachable.
+    fn branching() -> DFA {
+        DFA::try_from(
+            "states = [q0, q1, q2, q3]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q0,b = q2;
+    q2,a = q2;
+    q2,b = q2;
+    q3,a = q1;"
+                .to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn live_states() {
+        assert_eq!(
+            branching().live_states(),
+            HashSet::from(["q0".to_string(), "q1".to_string(), "q3".to_string()])
+        );
+    }
+
+    #[test]
+    fn reachable_states() {
+        assert_eq!(
+            branching().reachable_states(),
+            HashSet::from(["q0".to_string(), "q1".to_string(), "q2".to_string()])
+        );
+    }
+
+    #[test]
+    fn reachable_from_state() {
+        assert_eq!(
+            branching().reachable_from_state("q3"),
+            HashSet::from(["q3".to_string(), "q1".to_string()])
+        );
+        assert_eq!(
+            branching().reachable_from_state("q2"),
+            HashSet::from(["q2".to_string()])
+        );
+    }
+
+    #[test]
+    fn trim_removes_dead_branch() {
+        assert_eq!(
+            branching().trim(),
+            DFA::try_from(
+                "states = [q0, q1]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;"
+                    .to_string()
+            )
+            .unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod restrict_alphabet_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn removed_symbol_is_rejected() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let restricted = dfa.restrict_alphabet(&["a".to_string()]);
+
+        assert_eq!(restricted.alphabet, vec!["a"]);
+        assert!(restricted.accepts(&["a", "a"]));
+        assert!(dfa.accepts(&["a", "b"]));
+        assert!(!restricted.accepts(&["a", "b"]));
+        assert!(!restricted.accepts(&["b"]));
+    }
+}
+
+#[cfg(test)]
+mod transitions_iter_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn sorted_order() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert_eq!(
+            dfa.transitions_iter().collect::<Vec<_>>(),
+            vec![
+                ("q1", "a", "q2"),
+                ("q1", "b", "q1"),
+                ("q2", "a", "q2"),
+                ("q2", "b", "q2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn order_does_not_depend_on_insertion() {
+        let forward = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let mut backward = forward.clone();
+        backward.transition.clear();
+        for (start, symbol, end) in forward.transitions_iter().collect::<Vec<_>>().iter().rev() {
+            backward.set_transition(start, symbol, end).unwrap();
+        }
+        assert_eq!(
+            backward.transition.keys().collect::<Vec<_>>(),
+            forward.transition.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(String::from(&backward), String::from(&forward));
+        assert_eq!(forward.outgoing("q2"), vec![("a", "q2"), ("b", "q2")]);
+    }
+}
+
+#[cfg(test)]
+mod distances_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn big_dfa() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        assert_eq!(
+            dfa.distances(),
+            HashMap::from([
+                ("q3".to_string(), 0),
+                ("q2".to_string(), 1),
+                ("q5".to_string(), 2),
+                ("q1".to_string(), 3),
+                ("q4".to_string(), 3),
+            ])
+        );
+    }
+}
+
+#[cfg(test)]
+mod missing_transitions_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn total_dfa() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.missing_transitions(), vec![]);
+    }
+
+    #[test]
+    fn partial_dfa() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        let missing = dfa.missing_transitions();
+        assert_eq!(missing.len(), 7);
+        assert_eq!(missing[0], ("q1".to_string(), "a".to_string()));
+        assert!(!missing.contains(&("q2".to_string(), "b".to_string())));
+    }
+}
+
+#[cfg(test)]
+mod wildcard_tests {
+    use super::*;
+
+    #[test]
+    fn fills_unmentioned_symbols() {
+        let dfa = DFA::try_from(
+            "states = [q1, q2, q_sink]
+alphabet = [a, b, c]
+starting_state = q1
+accepting_states = [q2]
+transitions =
+    q1,_ = q_sink;
+    q1,a = q2;"
+                .to_string(),
+        )
+        .unwrap();
+
+        let mut tran: BTreeMap<(String, String), String> = BTreeMap::new();
+        tran.insert(("q1".to_string(), "a".to_string()), "q2".to_string());
+        tran.insert(("q1".to_string(), "b".to_string()), "q_sink".to_string());
+        tran.insert(("q1".to_string(), "c".to_string()), "q_sink".to_string());
+        assert_eq!(dfa.transition, tran);
+    }
+}
+
+#[cfg(test)]
+mod find_cycle_tests {
+    use super::*;
+
+    #[test]
+    fn acyclic() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1, q2]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q2]
+transitions =
+    q0,a = q1;
+    q0,b = q2;
+    q1,a = q2;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(dfa.find_cycle(), None);
+    }
+
+    #[test]
+    fn self_loop() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q1,b = q1;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(dfa.find_cycle(), Some(vec!["q1".to_string()]));
+    }
+
+    #[test]
+    fn longer_cycle() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1, q2]
+alphabet = [a]
+starting_state = q0
+accepting_states = [q2]
+transitions =
+    q0,a = q1;
+    q1,a = q2;
+    q2,a = q1;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            dfa.find_cycle(),
+            Some(vec!["q1".to_string(), "q2".to_string()])
+        );
+    }
+}
+
+#[cfg(test)]
+mod components_tests {
+    use super::*;
+
+    #[test]
+    fn unreachable_island() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1, q2, q3]
+alphabet = [a]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q1,a = q0;
+    q2,a = q3;
+    q3,a = q3;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            dfa.weakly_connected_components(),
+            vec![vec!["q0", "q1"], vec!["q2", "q3"]]
+        );
+    }
+
+    #[test]
+    fn connected() {
+        let dfa = DFA::try_from(std::fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.weakly_connected_components().len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod canonical_tests {
+    use std::{collections::hash_map::DefaultHasher, fs};
+
+    use super::*;
+
+    fn hash(dfa: &CanonicalDFA) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        dfa.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn isomorphic_dfas_hash_equal() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        let renamed = DFA::try_from(
+            "states = [e, d, c, b, a]
+alphabet = [b, a]
+starting_state = c
+accepting_states = [e]
+transitions =
+    a,a = b;
+    a,b = a;
+    b,a = b;
+    b,b = e;
+    c,a = b;
+    c,b = c;
+    d,a = b;
+    d,b = d;
+    e,a = a;
+    e,b = d;"
+                .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(dfa.canonical_form(), renamed.canonical_form());
+        let (dfa, renamed) = (CanonicalDFA::from(dfa), CanonicalDFA::from(renamed));
+        assert_eq!(hash(&dfa), hash(&renamed));
+        assert_eq!(HashSet::from([dfa, renamed]).len(), 1);
+    }
+
+    #[test]
+    fn different_dfas_differ() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        let mut other = dfa.clone();
+        other.accepting_states = vec!["q4".to_string()];
+        assert_ne!(CanonicalDFA::from(dfa), CanonicalDFA::from(other));
+    }
+}
+
+#[cfg(test)]
+mod accepts_empty_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn accepting_start() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert!(dfa.accepts_empty());
+        assert!(dfa.accepts(&[]));
+        assert_eq!(dfa.accepts_str(""), Ok(true));
+    }
+
+    #[test]
+    fn rejecting_start() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        assert!(!dfa.accepts_empty());
+        assert!(!dfa.accepts(&[]));
+        assert_eq!(dfa.accepts_str(""), Ok(false));
+    }
+
+    #[test]
+    fn run_is_just_start() {
+        for path in ["./test.dfa", "./big.dfa", "./three.dfa"] {
+            let dfa = DFA::try_from(fs::read_to_string(path).unwrap()).unwrap();
+            assert_eq!(
+                dfa.run(&[]),
+                Some(vec![dfa.starting_state.clone()]),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn without_transitions() {
+        // Nothing can be read from q1, so the empty word is all there is.
+        let dfa = DFA::try_from(
+            "states = [q1]
+alphabet = [a]
+starting_state = q1
+accepting_states = [q1]
+transitions ="
+                .to_string(),
+        )
+        .unwrap();
+        assert!(dfa.accepts(&[]));
+        assert_eq!(dfa.accepts_str(""), Ok(true));
+        assert!(!dfa.accepts(&["a"]));
+        assert_eq!(dfa.run(&["a"]), None);
+    }
+}
+
+#[cfg(test)]
+mod undeclared_symbols_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn declared() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert!(dfa.undeclared_symbols().is_empty());
+    }
+
+    #[test]
+    fn undeclared() {
+        let mut dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        dfa.transition
+            .insert(("q1".to_string(), "c".to_string()), "q2".to_string());
+        dfa.transition
+            .insert(("q2".to_string(), "c".to_string()), "q2".to_string());
+        assert_eq!(dfa.undeclared_symbols(), vec!["c"]);
+    }
+}
+
+#[cfg(test)]
+mod unused_symbols_tests {
+    use super::*;
+
+    #[test]
+    fn unused() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1]
+alphabet = [a, b, c]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q1,b = q0;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(dfa.unused_symbols(), vec!["c"]);
+    }
+}
+
+#[cfg(test)]
+mod neighbours_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn outgoing() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.outgoing("q5"), vec![("a", "q1"), ("b", "q4")]);
+        assert!(dfa.outgoing("q6").is_empty());
+    }
+
+    #[test]
+    fn incoming() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        assert_eq!(
+            dfa.incoming("q2"),
+            vec![("q1", "a"), ("q2", "a"), ("q3", "a"), ("q4", "a")]
+        );
+        assert_eq!(dfa.incoming("q3"), vec![("q3", "b")]);
+    }
+}
+
+#[cfg(test)]
+mod sections_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn reordered_sections() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let reordered = DFA::try_from(
+            "starting_state = q1
+transitions =
+    q2,b = q2;
+    q2,a = q2;
+    q1,a = q2;
+    q1,b = q1;
+accepting_states = [q1, q2]
+alphabet = [a, b]
+states = [q1, q2]"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(reordered, dfa);
+    }
+
+    #[test]
+    fn duplicate_section() {
+        assert_eq!(
+            DFA::try_from("states = [q1]\nstates = [q2]".to_string()),
+            Err("Duplicate section states".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_section() {
+        assert_eq!(
+            DFA::try_from("states = [q1]\nalphabet = [a]\nstarting_state = q1".to_string()),
+            Err("Missing section accepting_states".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_section() {
+        assert_eq!(
+            DFA::try_from("stats = [q1]".to_string()),
+            Err("Unknown section stats".to_string())
+        );
+    }
+}
+
+#[cfg(test)]
+mod minimize_tests {
+    use std::fs;
+
+    use super::*;
+
+    // Every word over `alphabet` of length at most `max`.
+    fn words(alphabet: &[String], max: usize) -> Vec<Vec<&str>> {
+        let mut words = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..max {
+            last = last
+                .iter()
+                .flat_map(|word: &Vec<&str>| {
+                    alphabet.iter().map(move |symbol| {
+                        let mut word = word.clone();
+                        word.push(symbol.as_str());
+                        word
+                    })
+                })
+                .collect();
+            words.extend(last.clone());
+        }
+        words
+    }
+
+    #[test]
+    fn merges_equivalent_states() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        let minimal = dfa.minimize();
+
+        assert_eq!(minimal.states, vec!["q1", "q2", "q5"]);
+        assert_eq!(minimal.starting_state, "q1");
+        for word in words(&dfa.alphabet, 6) {
+            assert_eq!(minimal.accepts(&word), dfa.accepts(&word), "{word:?}");
+        }
+    }
+
+    #[test]
+    fn partial_stays_partial() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1, q2, q3, q4]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q2, q4]
+transitions =
+    q0,a = q1;
+    q0,b = q3;
+    q1,a = q2;
+    q3,a = q4;"
+                .to_string(),
+        )
+        .unwrap();
+        let minimal = dfa.minimize();
+
+        assert_eq!(minimal.states, vec!["q0", "q1", "q2"]);
+        assert_eq!(minimal.transition.len(), 3);
+        for word in words(&dfa.alphabet, 4) {
+            assert_eq!(minimal.accepts(&word), dfa.accepts(&word), "{word:?}");
+        }
+    }
+
+    #[test]
+    fn dead_end_absorbs_missing_transitions() {
+        // `q2` accepts nothing, so it merges with the implicit dead state and
+        // takes over the transitions missing from `q0` and `q1`.
+        let dfa = DFA::try_from(
+            "states = [q0, q1, q2]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q1,b = q2;"
+                .to_string(),
+        )
+        .unwrap();
+        let minimal = dfa.minimize();
+
+        assert_eq!(minimal.states, vec!["q0", "q1", "q2"]);
+        assert!(minimal.missing_transitions().is_empty());
+        assert_eq!(minimal.step("q0", "b"), Some("q2"));
+        assert_eq!(minimal.step("q2", "a"), Some("q2"));
+        for word in words(&dfa.alphabet, 4) {
+            assert_eq!(minimal.accepts(&word), dfa.accepts(&word), "{word:?}");
+        }
+    }
+
+    #[test]
+    fn already_minimal() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.minimize().states.len(), 1);
+    }
+
+    #[test]
+    fn moore_matches_hopcroft() {
+        // Random DFAs are nearly always minimal already; their products with
+        // a sparse accepting set are not.
+        for seed in 0..50 {
+            let mut dfa = DFA::random(12, &["a", "b"], seed);
+            dfa.accepting_states.truncate(1);
+            let dfa = dfa.union(&DFA::random(3, &["a", "b"], seed + 100)).unwrap();
+            assert_eq!(
+                dfa.minimize_moore().canonical_form(),
+                dfa.minimize().canonical_form(),
+                "seed {seed}"
+            );
+        }
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.minimize_moore().states, vec!["q1", "q2", "q5"]);
+    }
+}
+
+#[cfg(test)]
+mod redundant_state_groups_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn groups_equivalent_states() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1, q2, q3]
+alphabet = [a]
+starting_state = q0
+accepting_states = [q1, q2]
+transitions =
+    q0,a = q1;
+    q1,a = q2;
+    q2,a = q2;
+    q3,a = q3;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(dfa.redundant_state_groups(), vec![vec!["q1", "q2"]]);
+    }
+
+    #[test]
+    fn agrees_with_minimize() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        let merged: usize = dfa
+            .redundant_state_groups()
+            .iter()
+            .map(|group| group.len() - 1)
+            .sum();
+        assert_eq!(
+            dfa.reachable_states().len() - merged,
+            dfa.minimize().states.len()
+        );
+    }
+
+    #[test]
+    fn minimal_has_none() {
+        let dfa = DFA::try_from(fs::read_to_string("./big.dfa").unwrap()).unwrap();
+        assert!(dfa.minimize().redundant_state_groups().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod dead_state_tests {
+    use super::*;
+
+    // Accepts words over {a, b} without a `b`, with `q_dead` catching the rest.
+    const NO_B: &str = "states = [q0, q_dead]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q0]
+dead_state = q_dead;
+transitions =
+    q0,a = q0;
+    q0,b = q_dead;
+    q_dead,a = q_dead;";
+
+    #[test]
+    fn parse() {
+        let dfa = DFA::try_from(NO_B).unwrap();
+        assert_eq!(dfa.dead_state.as_deref(), Some("q_dead"));
+        assert!(dfa
+            .to_string()
+            .contains("\ndead_state = q_dead\ntransitions ="));
+        assert_eq!(DFA::try_from(dfa.to_string()), Ok(dfa));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            DFA::try_from(NO_B.replace("dead_state = q_dead", "dead_state = q9")),
+            Err("Dead State q9 is not a valid state.".to_string())
+        );
+        assert_eq!(
+            DFA::try_from(NO_B.replace("[q0]", "[q0, q_dead]")),
+            Err("Dead State q_dead cannot be accepting.".to_string())
+        );
+        assert_eq!(
+            DFA::try_from(NO_B.replace("q_dead,a = q_dead", "q_dead,a = q0")),
+            Err("Dead State q_dead cannot leave on a to q0.".to_string())
+        );
+    }
+
+    #[test]
+    fn trim_keeps_dead_state() {
+        let trimmed = DFA::try_from(NO_B).unwrap().trim();
+        assert_eq!(trimmed.states, vec!["q0", "q_dead"]);
+        assert_eq!(trimmed.step("q0", "b"), Some("q_dead"));
+
+        let mut undeclared = DFA::try_from(NO_B).unwrap();
+        undeclared.dead_state = None;
+        assert_eq!(undeclared.trim().states, vec!["q0"]);
+    }
+
+    #[test]
+    fn editing() {
+        let mut dfa = DFA::try_from(NO_B).unwrap();
+        assert_eq!(
+            dfa.set_transition("q_dead", "b", "q0"),
+            Err("Dead State q_dead cannot leave on b to q0.".to_string())
+        );
+        dfa.remove_state("q_dead").unwrap();
+        assert_eq!(dfa.dead_state, None);
+    }
+
+    #[test]
+    fn complete_uses_dead_state() {
+        let dfa = DFA::try_from(NO_B).unwrap().complete();
+        assert_eq!(dfa.states.len(), 2);
+        assert_eq!(dfa.step("q_dead", "b"), Some("q_dead"));
+        assert!(dfa.missing_transitions().is_empty());
+    }
+
+    #[test]
+    fn complete_adds_dead_state() {
+        let mut dfa = DFA::try_from(NO_B).unwrap();
+        dfa.dead_state = None;
+        dfa.transition.remove(&("q0".to_string(), "b".to_string()));
+        let complete = dfa.trim().complete();
+        assert_eq!(complete.dead_state.as_deref(), Some("q1"));
+        assert_eq!(complete.step("q0", "b"), Some("q1"));
+        assert!(complete.missing_transitions().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod parse_collect_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn reports_every_error() {
+        let code = "states = [q0, q1]
+alphabet = [a, b]
+colour = red
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0 a = q1;
+    q0,b = q0;
+    q1,a = q9;
+    q1,b = q1;";
+        assert_eq!(
+            DFA::parse_collect(code),
+            Err(vec![
+                "Unknown section colour".to_string(),
+                "Unexpected Symbol 'a' Expected ',' or ']'".to_string(),
+                " Final state q9, in Transition q1,a -> q9 are invalid".to_string(),
+            ])
+        );
+        assert_eq!(
+            DFA::try_from(code),
+            Err("Unknown section colour".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_missing_sections() {
+        assert_eq!(
+            DFA::parse_collect("states = [q0]\nalphabet = [a, b"),
+            Err(vec![
+                "Unexpected End of File.".to_string(),
+                "Missing section alphabet".to_string(),
+                "Missing section starting_state".to_string(),
+                "Missing section accepting_states".to_string(),
+                "Missing section transitions".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn valid_file() {
+        let code = fs::read_to_string("./big.dfa").unwrap();
+        assert_eq!(
+            DFA::parse_collect(&code),
+            DFA::try_from(code).map_err(|e| vec![e])
+        );
+    }
+}
+
+#[cfg(test)]
+mod path_to_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn shortest_word() {
+        let dfa = DFA::contains_substring(&["a", "b"], &["a", "b", "a", "b"]);
+        assert_eq!(
+            dfa.path_to("q4"),
+            Some(
+                vec!["a", "b", "a", "b"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            )
+        );
+        assert_eq!(dfa.path_to("q0"), Some(vec![]));
+        for state in &dfa.states {
+            let word = dfa.path_to(state).unwrap();
+            assert_eq!(word.len(), dfa.distances()[state]);
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        let reachable = dfa.reachable_states();
+        for state in &dfa.states {
+            assert_eq!(dfa.path_to(state).is_some(), reachable.contains(state));
+        }
+        assert_eq!(dfa.path_to("q9"), None);
+    }
+}
+
+#[cfg(test)]
+mod accepts_str_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn single_char_alphabet() {
+        let dfa = DFA::contains_substring(&["a", "b"], &["b", "b"]);
+        assert_eq!(dfa.accepts_str("abba"), Ok(true));
+        assert_eq!(dfa.accepts_str("abab"), Ok(false));
+        assert_eq!(dfa.accepts_str(""), Ok(false));
+        assert_eq!(dfa.accepts_str("abc"), Ok(false));
+    }
+
+    #[test]
+    fn unicode_symbols() {
+        let dfa = DFA::contains_substring(&["α", "β"], &["β"]);
+        assert_eq!(dfa.accepts_str("ααβ"), Ok(true));
+        assert_eq!(dfa.accepts_str("αα"), Ok(false));
+    }
+
+    #[test]
+    fn multi_char_alphabet() {
+        let mut dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        dfa.alphabet.push("ab".to_string());
+        assert_eq!(
+            dfa.accepts_str("ab"),
+            Err(
+                "Symbol ab is not a single character, pass the word to accepts as a slice of symbols."
+                    .to_string()
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod degenerate_tests {
+    use super::*;
+
+    #[test]
+    fn empty_alphabet() {
+        let code = "states = [q0]
+alphabet = []
+starting_state = q0
+accepting_states = [q0]
+transitions =";
+        assert_eq!(
+            DFA::try_from(code),
+            Err("Alphabet cannot be empty.".to_string())
+        );
+    }
+
+    #[test]
+    fn empty_states() {
+        let code = "states = []
+alphabet = [a]
+starting_state = q0
+accepting_states = []
+transitions =";
+        assert_eq!(
+            DFA::try_from(code),
+            Err("States cannot be empty.".to_string())
+        );
+        assert_eq!(
+            DFA::parse_collect(code),
+            Err(vec![
+                "States cannot be empty.".to_string(),
+                "q0 is not a valid State.".to_string(),
+            ])
+        );
+    }
+}
+
+#[cfg(test)]
+mod index_of_language_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn matches_minimize() {
+        for code in ["./test.dfa", "./three.dfa", "./big.dfa"] {
+            let dfa = DFA::try_from(fs::read_to_string(code).unwrap()).unwrap();
+            assert_eq!(
+                dfa.index_of_language(),
+                dfa.minimize().states.len(),
+                "{code}"
+            );
+        }
+        for seed in 0..20 {
+            let dfa = DFA::random(12, &["a", "b"], seed)
+                .union(&DFA::random(8, &["a", "b"], seed + 1))
+                .unwrap();
+            assert_eq!(
+                dfa.index_of_language(),
+                dfa.minimize().states.len(),
+                "{seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn counts_residues() {
+        assert_eq!(DFA::modulo_length(&["a", "b"], 5).index_of_language(), 5);
+    }
+}
+
+#[cfg(test)]
+mod scc_tests {
+    use super::*;
+
+    #[test]
+    fn groups_mutually_reachable_states() {
+        // q0 <-> q1 loop, q2 loops on itself, q3 is a dead end.
+        let dfa = DFA::try_from(
+            "states = [q0, q1, q2, q3]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q2]
+transitions =
+    q0,a = q1;
+    q1,a = q0;
+    q1,b = q2;
+    q2,a = q2;
+    q2,b = q3;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            dfa.strongly_connected_components(),
+            vec![vec!["q0", "q1"], vec!["q2"], vec!["q3"]]
+        );
+    }
+
+    #[test]
+    fn covers_every_state() {
+        let dfa = DFA::random(200, &["a", "b"], 11);
+        let components = dfa.strongly_connected_components();
+        let mut states: Vec<&String> = components.iter().flatten().collect();
+        states.sort();
+        let mut expected: Vec<&String> = dfa.states.iter().collect();
+        expected.sort();
+        assert_eq!(states, expected);
+        // Members of one component reach each other.
+        for component in &components {
+            let mut from = dfa.clone();
+            from.starting_state = component[0].clone();
+            let reach = from.reachable_states();
+            assert!(component.iter().all(|state| reach.contains(state)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod adjacency_matrix_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_dfa() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let (states, matrix) = dfa.adjacency_matrix();
+        assert_eq!(states, vec!["q1", "q2"]);
+        assert_eq!(matrix.len(), 2);
+        assert!(matrix.iter().all(|row| row.len() == 2));
+        // q1 -a-> q2, q1 -b-> q1, and q2 loops on both, `a` coming first.
+        assert_eq!(matrix, vec![vec![Some(1), Some(0)], vec![None, Some(0)]]);
+    }
+
+    #[test]
+    fn partial_rows() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        let (states, matrix) = dfa.adjacency_matrix();
+        let q2 = states.iter().position(|state| state == "q2").unwrap();
+        let b = dfa
+            .alphabet
+            .iter()
+            .position(|symbol| symbol == "b")
+            .unwrap();
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                let expected = (i == q2 && j == q2).then_some(b);
+                assert_eq!(*entry, expected, "{i} {j}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod parse_many_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn two_machines() {
+        let first = fs::read_to_string("./test.dfa").unwrap();
+        let second = fs::read_to_string("./three.dfa").unwrap();
+        let code = format!("{first}\n---\n{second}\n---\n");
+        assert_eq!(
+            DFA::parse_many(&code),
+            Ok(vec![
+                DFA::try_from(first).unwrap(),
+                DFA::try_from(second).unwrap()
+            ])
+        );
+    }
+
+    #[test]
+    fn errors_name_the_machine() {
+        let first = fs::read_to_string("./test.dfa").unwrap();
+        let code = format!("{first}---\nstates = [q1]\nalphabet = [a]\nstarting_state = q9\naccepting_states = []\ntransitions =");
+        assert_eq!(
+            DFA::parse_many(&code),
+            Err("DFA 2: q9 is not a valid State.".to_string())
+        );
+        assert_eq!(DFA::parse_many("---\n"), Err("No DFA found.".to_string()));
+    }
+
+    #[test]
+    fn lines_count_from_the_top_of_the_file() {
+        let first = fs::read_to_string("./test.dfa").unwrap();
+        let code = format!("{first}---\nstates = [q1, q2]\nalphabet = [a]\nstarting_state = q1\naccepting_states = []\ntransitions =\n    q1,a = q1;\n    q1,a = q2;\n");
+        let offset = first.lines().count() + 1;
+        assert_eq!(
+            DFA::parse_many(&code),
+            Err(format!(
+                "DFA 2: State q1 on 'a' goes to both q1 (line {}) and q2 (line {})",
+                offset + 6,
+                offset + 7
+            ))
+        );
+    }
+}
+
+#[cfg(test)]
+mod fingerprint_tests {
+    use super::*;
+
+    #[test]
+    fn same_language_same_fingerprint() {
+        // Words containing `bb`: built from KMP, written by hand with extra
+        // states and odd names, and unioned with itself.
+        let kmp = DFA::contains_substring(&["a", "b"], &["b", "b"]);
+        let by_hand = DFA::try_from(
+            "states = [start, one_b, one_b_again, done, lost]
+alphabet = [b, a]
+starting_state = start
+accepting_states = [done]
+transitions =
+    start,a = start;
+    start,b = one_b;
+    one_b,a = one_b_again;
+    one_b,b = done;
+    one_b_again,a = start;
+    one_b_again,b = one_b;
+    done,_ = done;
+    lost,a = done;"
+                .to_string(),
+        )
+        .unwrap();
+        let union = kmp.union(&kmp).unwrap();
+        assert_eq!(kmp.language_fingerprint(), by_hand.language_fingerprint());
+        assert_eq!(kmp.language_fingerprint(), union.language_fingerprint());
+        assert_ne!(
+            kmp.language_fingerprint(),
+            DFA::contains_substring(&["a", "b"], &["a", "b"]).language_fingerprint()
+        );
+    }
+
+    #[test]
+    fn partial_matches_total() {
+        let partial = DFA::try_from(
+            "states = [q0]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q0]
+transitions =
+    q0,a = q0;"
+                .to_string(),
+        )
+        .unwrap();
+        let total = partial.complete();
+        assert_eq!(partial.language_fingerprint(), total.language_fingerprint());
+    }
+}
+
+#[cfg(test)]
+mod lenient_tests {
+    use super::*;
+
+    const UNDECLARED: &str = "states = [q1, q2]
+alphabet = [a, b]
+starting_state = q1
+accepting_states = [q2]
+transitions =
+    q1,a = q2;
+    q2,b = q3;
+    q3,a = q1;";
+
+    #[test]
+    fn adds_undeclared_states() {
+        assert!(DFA::try_from(UNDECLARED).is_err());
+        let (dfa, warnings) = DFA::try_from_lenient(UNDECLARED).unwrap();
+        assert_eq!(dfa.states, vec!["q1", "q2", "q3"]);
+        assert_eq!(
+            warnings,
+            vec!["State q3 is not declared, adding it to states.".to_string()]
+        );
+        assert!(dfa.accepts(&["a", "b", "a", "a"]));
+    }
+
+    #[test]
+    fn other_errors_remain() {
+        let (dfa, warnings) =
+            DFA::try_from_lenient(&std::fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(
+            Ok(dfa),
+            DFA::try_from(std::fs::read_to_string("./test.dfa").unwrap())
+        );
+        assert_eq!(
+            DFA::try_from_lenient(
+                &UNDECLARED.replace("accepting_states = [q2]", "accepting_states = [q4]")
+            ),
+            Err("Accepting State q4 is not a valid state.\n".to_string())
+        );
     }
 }
 
-impl Into<String> for DFA {
-    fn into(self) -> String {
-        let mut parts = Vec::new();
-
-        parts.push(format!("states = [{}]", self.states.join(", ")));
-        parts.push(format!("alphabet = [{}]", self.alphabet.join(", ")));
-        parts.push(format!("starting_state = {}", self.starting_state));
-        parts.push(format!(
-            "accepting_states = [{}]",
-            self.accepting_states.join(", ")
-        ));
-        parts.push("transitions =".to_string());
-
-        let transitions = self
-            .transition
-            .iter()
-            .map(|((start, alphabet), end)| format!("    {start},{alphabet} = {end};"))
-            .collect::<Vec<_>>()
-            .join("\n");
+#[cfg(test)]
+mod random_tests {
+    use super::*;
 
-        parts.push(transitions);
+    #[test]
+    fn same_seed_same_dfa() {
+        let a = DFA::random(8, &["a", "b", "c"], 42);
+        let b = DFA::random(8, &["a", "b", "c"], 42);
+        assert_eq!(a, b);
+        assert_ne!(a, DFA::random(8, &["a", "b", "c"], 43));
+    }
 
-        parts.join("\n")
+    #[test]
+    fn total_connected_and_valid() {
+        for seed in 0..20 {
+            let dfa = DFA::random(10, &["0", "1"], seed);
+            assert!(dfa.missing_transitions().is_empty());
+            assert_eq!(dfa.reachable_states().len(), 10);
+            assert_eq!(DFA::try_from(String::from(&dfa)), Ok(dfa));
+        }
     }
 }
 
-fn whitespace(code: &mut Peekable<CharIndices>) {
-    while code.next_if(|(_, c)| c.is_whitespace()).is_some() {}
-}
+#[cfg(test)]
+mod step_tests {
+    use super::*;
 
-fn word(code: &mut Peekable<CharIndices>) -> String {
-    whitespace(code);
-    let word = std::iter::from_fn(|| {
-        code.by_ref()
-            .next_if(|(_, ch)| ch.is_alphanumeric() || *ch == '_')
-    })
-    .map(|(_, c)| c)
-    .collect();
-    whitespace(code);
-    word
+    #[test]
+    fn defined_and_undefined() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(dfa.step("q0", "a"), Some("q1"));
+        assert_eq!(dfa.step("q0", "b"), None);
+        assert_eq!(dfa.step("q2", "a"), None);
+    }
 }
 
-fn list(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
-    whitespace(code);
+#[cfg(test)]
+mod editing_tests {
+    use super::*;
 
-    match code.next() {
-        Some((_, '[')) => (),
-        Some((_, x)) => return Err(format!("Unexpected Symbol '{x}' Expected [")),
-        None => return Err("Unexpected End of File.".to_string()),
-    };
-    let mut list = vec![];
-    loop {
-        let item = word(code);
-        list.push(item);
-        match code.next() {
-            Some((_, ',')) => (),
-            Some((_, ']')) => break,
-            Some((_, x)) => return Err(format!("Unexpected Symbol '{x}' Expected ,")),
-            None => return Err("Unexpected End of File.".to_string()),
-        }
+    fn dfa() -> DFA {
+        DFA::try_from(
+            "states = [q0, q1]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q1,a = q0;"
+                .to_string(),
+        )
+        .unwrap()
     }
-    Ok(list)
-}
 
-fn keyword(code: &mut Peekable<CharIndices>, keyword: &str) -> Result<bool, String> {
-    match word(code) {
-        x if x == keyword => return Ok(true),
-        x => Err(format!("Expected {keyword}")),
+    #[test]
+    fn add_state_picks_unused_name() {
+        let mut dfa = dfa();
+        assert_eq!(dfa.add_state(), "q2");
+        assert_eq!(dfa.states, vec!["q0", "q1", "q2"]);
     }
-}
 
-fn char(code: &mut Peekable<CharIndices>, ch: char) -> Result<bool, String> {
-    whitespace(code);
-    let result = match code.next() {
-        Some((_, x)) if x == ch => Ok(true),
-        Some((_, x)) => Err(format!("Unexpected Symbol '{x}' Expected {ch}")),
-        None => Err("Unexpected End of file.".to_string()),
-    };
-    whitespace(code);
-    return result;
-}
+    #[test]
+    fn remove_state_drops_its_transitions() {
+        let mut dfa = dfa();
+        dfa.remove_state("q1").unwrap();
+        assert_eq!(dfa.states, vec!["q0"]);
+        assert!(dfa.accepting_states.is_empty());
+        assert!(dfa.transition.is_empty());
+        assert!(dfa.remove_state("q0").is_err());
+        assert!(dfa.remove_state("q5").is_err());
+    }
 
-fn states(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
-    keyword(code, "states")?;
-    char(code, '=')?;
-    list(code)
-}
+    #[test]
+    fn set_transition_is_validated() {
+        let mut dfa = dfa();
+        dfa.set_transition("q1", "b", "q1").unwrap();
+        assert_eq!(dfa.step("q1", "b"), Some("q1"));
+        assert_eq!(
+            dfa.set_transition("q1", "c", "q1"),
+            Err("c is not in the alphabet.".to_string())
+        );
+        assert!(dfa.set_transition("q1", "a", "q9").is_err());
+    }
 
-fn alphabet(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
-    keyword(code, "alphabet")?;
-    char(code, '=')?;
-    list(code)
-}
+    // q1 and q2 both go back to q0 on `a`, so they can be merged, while q0
+    // and q2 cannot as q0 goes to q1 on `a`.
+    fn mergeable() -> DFA {
+        DFA::try_from(
+            "states = [q0, q1, q2]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q2]
+transitions =
+    q0,a = q1;
+    q0,b = q2;
+    q1,a = q0;
+    q2,a = q0;
+    q2,b = q2;"
+                .to_string(),
+        )
+        .unwrap()
+    }
 
-fn starting_state(code: &mut Peekable<CharIndices>) -> Result<String, String> {
-    keyword(code, "starting_state")?;
-    char(code, '=')?;
-    Ok(word(code))
-}
+    #[test]
+    fn merge_states_redirects_transitions() {
+        let merged = mergeable().merge_states("q1", "q2").unwrap();
+        assert_eq!(merged.states, vec!["q0", "q1"]);
+        assert_eq!(merged.accepting_states, vec!["q1"]);
+        assert_eq!(merged.step("q0", "b"), Some("q1"));
+        assert_eq!(merged.step("q1", "a"), Some("q0"));
+        assert_eq!(merged.step("q1", "b"), Some("q1"));
+    }
 
-fn accepting_states(code: &mut Peekable<CharIndices>) -> Result<Vec<String>, String> {
-    keyword(code, "accepting_states")?;
-    char(code, '=')?;
-    list(code)
+    #[test]
+    fn merge_states_rejects_conflicts() {
+        assert_eq!(
+            mergeable().merge_states("q0", "q2"),
+            Err("Merging q2 into q0 makes q0,a go to both q1 and q0.".to_string())
+        );
+        assert_eq!(
+            mergeable().merge_states("q0", "q9"),
+            Err("q9 is not a valid State.".to_string())
+        );
+    }
 }
 
-fn transitions(
-    code: &mut Peekable<CharIndices>,
-) -> Result<HashMap<(String, String), String>, String> {
-    keyword(code, "transitions")?;
-    char(code, '=')?;
+#[cfg(test)]
+mod trap_states_tests {
+    use super::*;
 
-    let mut transitions = HashMap::<(String, String), String>::new();
-    while code.peek().is_some() {
-        let start_state = word(code);
-        match code.next() {
-            Some((_, ',')) => (),
-            Some((_, ']')) => break,
-            Some((_, x)) => return Err(format!("Unexpected Symbol '{x}' Expected ',' or ']'")),
-            None => return Err("Unexpected End of File.".to_string()),
-        }
-        let input = word(code);
-        char(code, '=')?;
+    #[test]
+    fn finds_sink_of_completed_dfa() {
+        // Words starting with `a`; a leading `b` falls into `dead`.
+        let dfa = DFA::try_from(
+            "states = [q0, q1, dead]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q0,b = dead;
+    q1,_ = q1;
+    dead,_ = dead;"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(dfa.trap_states(), vec!["dead"]);
+    }
 
-        let final_state = word(code);
-        char(code, ';')?;
-        whitespace(code);
-        transitions.insert((start_state, input), final_state);
+    #[test]
+    fn partial_self_loop_is_not_a_trap() {
+        let dfa = DFA::try_from(
+            "states = [q0]
+alphabet = [a, b]
+starting_state = q0
+accepting_states = []
+transitions =
+    q0,a = q0;"
+                .to_string(),
+        )
+        .unwrap();
+        assert!(dfa.trap_states().is_empty());
     }
-    Ok(transitions)
 }
 
 #[cfg(test)]
-mod states_tests {
+mod empty_transitions_tests {
     use super::*;
 
     #[test]
-    fn missing_states_keyword() {
-        assert_eq!(
-            states(&mut "state = [q1, q2,q3, q4, q5]".char_indices().peekable()),
-            Err("Expected states".to_string()),
-        );
+    fn transitions_at_end_of_file() {
+        let dfa = DFA::try_from(
+            "states = [q0, q1]
+alphabet = [a]
+starting_state = q0
+accepting_states = [q0]
+transitions ="
+                .to_string(),
+        )
+        .unwrap();
+        assert!(dfa.transition.is_empty());
+        assert!(dfa.accepts(&[]));
+        assert!(!dfa.accepts(&["a"]));
     }
 
     #[test]
-    fn missing_equal_symbol() {
-        assert_eq!(
-            states(&mut "states , [q1, q2,q3, q4, q5]".char_indices().peekable()),
-            Err("Unexpected Symbol ',' Expected =".to_string()),
-        );
+    fn transitions_followed_by_section() {
+        let dfa = DFA::try_from(
+            "states = [q0]
+alphabet = [a]
+starting_state = q0
+transitions =
+accepting_states = []
+"
+            .to_string(),
+        )
+        .unwrap();
+        assert!(dfa.transition.is_empty());
+        assert!(!dfa.accepts(&[]));
     }
 
     #[test]
-    fn valid_parse() {
+    fn stray_symbol_is_an_error() {
+        let result = DFA::try_from(
+            "states = [q0]
+alphabet = [a]
+starting_state = q0
+accepting_states = []
+transitions = ;"
+                .to_string(),
+        );
         assert_eq!(
-            states(&mut "states = [q1, q2,q3, q4, q5]".char_indices().peekable()).unwrap(),
-            vec!["q1", "q2", "q3", "q4", "q5"]
+            result,
+            Err("Unexpected Symbol ';' Expected a state".to_string())
         );
     }
 }
 
 #[cfg(test)]
-mod alphabet_tests {
+mod unicode_tests {
     use super::*;
 
     #[test]
-    fn missing_states_keyword() {
-        assert_eq!(
-            alphabet(&mut "alphabett = [a,b]".char_indices().peekable()),
-            Err("Expected alphabet".to_string()),
-        );
+    fn non_ascii_names() {
+        let code = "states = [qα, qβ, 状态]
+alphabet = [α-γ, ü]
+starting_state = qα
+accepting_states = [状态]
+transitions =
+    qα,α = qβ;
+    qβ,β-γ = 状态;
+    状态,ü = *qα;";
+        let dfa: DFA = code.parse().unwrap();
+        assert_eq!(dfa.alphabet, vec!["α", "β", "γ", "ü"]);
+        assert_eq!(dfa.accepting_states, vec!["状态", "qα"]);
+        dfa.check(&[&[], &["α", "γ"], &["α", "β", "ü"]], &[&["β"], &["α"]])
+            .unwrap();
+        assert_eq!(DFA::try_from(String::from(&dfa)), Ok(dfa));
     }
 
     #[test]
-    fn missing_equal_symbol() {
+    fn non_ascii_unexpected_symbol() {
+        let code = "states → [q0]";
         assert_eq!(
-            alphabet(&mut "alphabet , [a,b]".char_indices().peekable()),
-            Err("Unexpected Symbol ',' Expected =".to_string()),
+            code.parse::<DFA>(),
+            Err("Unexpected Symbol 's' Expected a section".to_string())
         );
-    }
-
-    #[test]
-    fn valid_parse() {
+        let code = "states = [q0 → q1]";
         assert_eq!(
-            alphabet(&mut "alphabet = [a,b]".char_indices().peekable()).unwrap(),
-            vec!["a", "b"]
+            code.parse::<DFA>(),
+            Err("Unexpected Symbol '→' Expected ,".to_string())
         );
     }
 }
 
 #[cfg(test)]
-mod starting_state_tests {
+mod name_tests {
+    use std::fs;
+
     use super::*;
 
     #[test]
-    fn missing_states_keyword() {
-        assert_eq!(
-            starting_state(&mut "starting_statea = q1".char_indices().peekable()),
-            Err("Expected starting_state".to_string()),
+    fn named() {
+        let code = format!(
+            "name = \"Ends in b\";\n{}",
+            fs::read_to_string("./test.dfa").unwrap()
         );
+        let dfa: DFA = code.parse().unwrap();
+        assert_eq!(dfa.name.as_deref(), Some("Ends in b"));
+        assert!(String::from(&dfa).starts_with("name = \"Ends in b\"\nstates = "));
+        assert_eq!(DFA::try_from(String::from(&dfa)), Ok(dfa));
     }
 
     #[test]
-    fn missing_equal_symbol() {
+    fn unnamed() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        assert_eq!(dfa.name, None);
+        assert!(String::from(&dfa).starts_with("states = "));
+    }
+
+    #[test]
+    fn unterminated() {
         assert_eq!(
-            starting_state(&mut "starting_state , q1".char_indices().peekable()),
-            Err("Unexpected Symbol ',' Expected =".to_string()),
+            "name = \"Ends in b\nstates = [q0]".parse::<DFA>(),
+            Err("Unterminated name".to_string())
         );
     }
 
     #[test]
-    fn valid_parse() {
+    fn quote_in_name() {
+        let dfa = DFA::builder()
+            .name("The \"b\" machine")
+            .states(&["q0"])
+            .alphabet(&["b"])
+            .starting_state("q0")
+            .build();
         assert_eq!(
-            starting_state(&mut "starting_state = q1".char_indices().peekable()).unwrap(),
-            "q1"
+            dfa,
+            Err(r#"Name "The \"b\" machine" cannot contain '"' or a line break."#.to_string())
         );
     }
 }
 
 #[cfg(test)]
-mod accepting_states_tests {
+mod states_with_acceptance_tests {
+    use std::fs;
+
     use super::*;
 
     #[test]
-    fn missing_states_keyword() {
+    fn all_accepting() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
         assert_eq!(
-            accepting_states(
-                &mut "accepting_statets = [q1, q2,q3, q4, q5]"
-                    .char_indices()
-                    .peekable()
-            ),
-            Err("Expected accepting_states".to_string()),
+            dfa.states_with_acceptance().collect::<Vec<_>>(),
+            vec![("q1", true), ("q2", true)]
         );
     }
 
     #[test]
-    fn missing_equal_symbol() {
+    fn mixed() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
         assert_eq!(
-            accepting_states(
-                &mut "accepting_states , [q1, q2,q3, q4, q5]"
-                    .char_indices()
-                    .peekable()
-            ),
-            Err("Unexpected Symbol ',' Expected =".to_string()),
+            dfa.states_with_acceptance().collect::<Vec<_>>(),
+            vec![("q1", true), ("q2", true), ("q3", false), ("q4", false)]
         );
     }
+}
+
+#[cfg(test)]
+mod is_accepting_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn matches_accepting_states() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        assert!(dfa.is_accepting("q1"));
+        assert!(!dfa.is_accepting("q3"));
+        assert!(!dfa.is_accepting("q9"));
+    }
 
     #[test]
-    fn valid_parse() {
-        assert_eq!(
-            accepting_states(
-                &mut "accepting_states = [q1, q2,q3, q4, q5]"
-                    .char_indices()
-                    .peekable()
-            )
-            .unwrap(),
-            vec!["q1", "q2", "q3", "q4", "q5"]
-        );
+    fn accepting_changed_rebuilds_the_set() {
+        let mut dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        assert!(!dfa.is_accepting("q3"));
+        dfa.accepting_states.push("q3".to_string());
+        dfa.accepting_changed();
+        assert!(dfa.is_accepting("q3"));
+        let mut other = dfa.clone();
+        other.accepting_states.clear();
+        assert!(!other.is_accepting("q3"));
+        assert!(dfa.is_accepting("q3"));
     }
 }
 
 #[cfg(test)]
-mod transitions_tests {
+mod modulo_length_tests {
     use super::*;
 
     #[test]
-    fn valid_parse() {
-        let mut tran: HashMap<(String, String), String> = HashMap::new();
-        tran.insert(("q1".to_string(), "a".to_string()), "q2".to_string());
-        tran.insert(("q1".to_string(), "b".to_string()), "q1".to_string());
-        tran.insert(("q2".to_string(), "a".to_string()), "q1".to_string());
-        tran.insert(("q2".to_string(), "b".to_string()), "q2".to_string());
-        assert_eq!(
-            transitions(
-                &mut r#"
-transitions =
-    q1,a = q2;
-    q1,b = q1;
-    q2,a = q1;
-    q2,b = q2;
-"#
-                .char_indices()
-                .peekable()
-            )
-            .unwrap(),
-            tran
-        );
+    fn multiples_of_three() {
+        let dfa = DFA::modulo_length(&["a", "b"], 3);
+        assert_eq!(dfa.states.len(), 3);
+        assert!(dfa.missing_transitions().is_empty());
+        for length in 0..10 {
+            let word = vec!["b"; length];
+            assert_eq!(dfa.accepts(&word), length % 3 == 0, "{length}");
+        }
+        dfa.check(&[&["a", "b", "a"]], &[&["a", "b"], &["b", "a", "b", "a"]])
+            .unwrap();
+    }
+
+    #[test]
+    fn one_accepts_everything() {
+        let dfa = DFA::modulo_length(&["a"], 1);
+        dfa.check(&[&[], &["a"], &["a", "a"]], &[]).unwrap();
     }
 }
 
 #[cfg(test)]
-mod dfa_io_tests {
-    use std::fs;
-
+mod contains_substring_tests {
     use super::*;
 
     #[test]
-    fn io_test() {
-        let code = fs::read_to_string("./test.dfa").unwrap();
-        let dfa = DFA::try_from(code.clone());
-        dfa.expect("Error parsing dfa");
+    fn finds_pattern_anywhere() {
+        let dfa = DFA::contains_substring(&["a", "b", "c"], &["a", "b", "a"]);
+        dfa.check(
+            &[
+                &["a", "b", "a"],
+                &["c", "c", "a", "b", "a", "c", "c"],
+                &["a", "b", "a", "b", "a"],
+                &["a", "a", "b", "a"],
+                &["a", "b", "a", "b", "c"],
+            ],
+            &[
+                &[],
+                &["a", "b"],
+                &["a", "b", "b", "a"],
+                &["a", "b", "c", "a"],
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn overlapping_prefix() {
+        // After `aab` fails on `a`, `aa` must still count as progress.
+        let dfa = DFA::contains_substring(&["a", "b"], &["a", "a", "b"]);
+        dfa.check(
+            &[&["a", "a", "a", "b"], &["b", "a", "a", "a", "b"]],
+            &[&["a", "b", "a", "b"]],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn already_minimal() {
+        for pattern in [
+            vec!["a"],
+            vec!["a", "b", "a", "b"],
+            vec!["b", "b", "a", "b"],
+        ] {
+            let dfa = DFA::contains_substring(&["a", "b"], &pattern);
+            assert_eq!(dfa.states.len(), pattern.len() + 1);
+            assert_eq!(dfa.minimize().states.len(), dfa.states.len(), "{pattern:?}");
+        }
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let pattern = ["a", "b", "a", "a", "b"];
+        let dfa = DFA::contains_substring(&["a", "b"], &pattern);
+        for bits in 0..1 << 9 {
+            let word: Vec<&str> = (0..9)
+                .map(|i| if bits >> i & 1 == 1 { "a" } else { "b" })
+                .collect();
+            let contains = word.windows(pattern.len()).any(|window| window == pattern);
+            assert_eq!(dfa.accepts(&word), contains, "{word:?}");
+        }
+    }
+
+    #[test]
+    fn empty_pattern_accepts_everything() {
+        let dfa = DFA::contains_substring(&["a"], &[]);
+        dfa.check(&[&[], &["a"]], &[]).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "c is not in the alphabet.")]
+    fn pattern_outside_alphabet() {
+        DFA::contains_substring(&["a", "b"], &["a", "c"]);
     }
 }
@@ -1,5 +1,5 @@
-use crate::dfa::DFA;
-use std::collections::HashMap;
+use crate::{dfa::DFA, nfa::NFA};
+use std::{collections::HashMap, fmt};
 
 #[derive(Debug, PartialEq)]
 pub struct Graph {
@@ -10,7 +10,7 @@ pub struct Graph {
 impl From<DFA> for Graph {
     fn from(dfa: DFA) -> Graph {
         let mut adj_mat: HashMap<(String, String), Vec<String>> = HashMap::new();
-        dfa.transition.iter().for_each(|((start, alphabet), end)| {
+        dfa.transitions_iter().for_each(|(start, alphabet, end)| {
             adj_mat
                 .entry((start.to_string(), end.to_string()))
                 .or_default()
@@ -23,11 +23,160 @@ impl From<DFA> for Graph {
     }
 }
 
+impl Graph {
+    /// `(in_degree, out_degree)` of every node, counting edges between
+    /// distinct pairs of nodes rather than symbols. A self-loop counts
+    /// towards both.
+    pub fn degrees(&self) -> HashMap<String, (usize, usize)> {
+        let mut degrees: HashMap<String, (usize, usize)> = self
+            .nodes
+            .iter()
+            .map(|node| (node.clone(), (0, 0)))
+            .collect();
+        for (start, end) in self.adj_mat.keys() {
+            degrees.entry(end.clone()).or_default().0 += 1;
+            degrees.entry(start.clone()).or_default().1 += 1;
+        }
+        degrees
+    }
+
+    /// Every edge as `(from, to, symbols)`, sorted by `from` and then `to`.
+    pub fn edges(&self) -> Vec<(String, String, Vec<String>)> {
+        let mut edges: Vec<_> = self
+            .adj_mat
+            .iter()
+            .map(|((start, end), symbols)| (start.clone(), end.clone(), symbols.clone()))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    /// Groups of nodes connected when edges are treated as undirected.
+    /// Components and their members follow the order of `nodes`.
+    pub fn weakly_connected_components(&self) -> Vec<Vec<String>> {
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let index: HashMap<&str, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.as_str(), i))
+            .collect();
+        let mut parent: Vec<usize> = (0..self.nodes.len()).collect();
+        for (start, end) in self.adj_mat.keys() {
+            if let (Some(&a), Some(&b)) = (index.get(start.as_str()), index.get(end.as_str())) {
+                let (a, b) = (find(&mut parent, a), find(&mut parent, b));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut components: Vec<Vec<String>> = vec![];
+        let mut component_of_root = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let root = find(&mut parent, i);
+            let component = *component_of_root.entry(root).or_insert_with(|| {
+                components.push(vec![]);
+                components.len() - 1
+            });
+            components[component].push(node.clone());
+        }
+        components
+    }
+}
+
+/// Joins `symbols` with `, `, single characters first and each group sorted,
+/// collapsing each run of three or more consecutive characters into a class,
+/// so `a, b, c, d, x` becomes `[a-d], x`.
+pub fn label(symbols: &[String]) -> String {
+    let char_of = |symbol: &str| {
+        let mut chars = symbol.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    let mut sorted: Vec<&String> = symbols.iter().collect();
+    sorted.sort_by_key(|symbol| (char_of(symbol).is_none(), *symbol));
+    let mut parts = vec![];
+    let mut i = 0;
+    while i < sorted.len() {
+        let mut j = i + 1;
+        if let Some(first) = char_of(sorted[i]) {
+            while j < sorted.len()
+                && char_of(sorted[j]).map(u32::from) == Some(u32::from(first) + (j - i) as u32)
+            {
+                j += 1;
+            }
+        }
+        if j - i >= 3 {
+            parts.push(format!("[{}-{}]", sorted[i], sorted[j - 1]));
+        } else {
+            j = i + 1;
+            parts.push(sorted[i].clone());
+        }
+        i = j;
+    }
+    parts.join(", ")
+}
+
+// ε-transitions are labelled `ε`; an edge to several targets becomes one
+// edge per target.
+impl From<&NFA> for Graph {
+    fn from(nfa: &NFA) -> Graph {
+        let mut transitions: Vec<_> = nfa.transition.iter().collect();
+        transitions.sort();
+        let mut adj_mat: HashMap<(String, String), Vec<String>> = HashMap::new();
+        for ((start, alphabet), ends) in transitions {
+            for end in ends {
+                adj_mat
+                    .entry((start.clone(), end.clone()))
+                    .or_default()
+                    .push(alphabet.clone().unwrap_or_else(|| "ε".to_string()));
+            }
+        }
+        Graph {
+            nodes: nfa.states.clone(),
+            adj_mat,
+        }
+    }
+}
+
+// Lists every node followed by one indented `target: symbols` line per
+// outgoing edge, sorted by target.
+impl fmt::Display for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{node}")?;
+            let mut edges: Vec<_> = self
+                .adj_mat
+                .iter()
+                .filter(|((start, _), _)| start == node)
+                .map(|((_, end), symbols)| (end, symbols))
+                .collect();
+            edges.sort();
+            for (end, symbols) in edges {
+                write!(f, "\n    {end}: {}", symbols.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod graph_tests {
     use std::{collections::HashMap, fs};
 
-    use crate::{dfa::DFA, graph::Graph};
+    use crate::{
+        dfa::DFA,
+        graph::{label, Graph},
+        nfa::NFA,
+    };
 
     #[test]
     fn dfa_to_graph() {
@@ -47,4 +196,75 @@ mod graph_tests {
             },
         );
     }
+
+    #[test]
+    fn display() {
+        let dfa_txt = fs::read_to_string("test.dfa").unwrap();
+        assert_eq!(
+            Graph::from(DFA::try_from(dfa_txt).unwrap()).to_string(),
+            "q1\n    q1: b\n    q2: a\nq2\n    q2: a, b"
+        );
+    }
+
+    #[test]
+    fn components_in_order() {
+        let graph = Graph {
+            nodes: vec!["q0".to_string(), "q1".to_string(), "q2".to_string()],
+            adj_mat: HashMap::from([(("q2".to_string(), "q0".to_string()), vec!["a".to_string()])]),
+        };
+        assert_eq!(
+            graph.weakly_connected_components(),
+            vec![vec!["q0", "q2"], vec!["q1"]]
+        );
+    }
+
+    #[test]
+    fn degrees() {
+        let dfa_txt = fs::read_to_string("test.dfa").unwrap();
+        assert_eq!(
+            Graph::from(DFA::try_from(dfa_txt).unwrap()).degrees(),
+            HashMap::from([("q1".to_string(), (1, 2)), ("q2".to_string(), (2, 1))])
+        );
+    }
+
+    #[test]
+    fn edges() {
+        let dfa_txt = fs::read_to_string("test.dfa").unwrap();
+        let edge = |start: &str, end: &str, symbols: &[&str]| {
+            (
+                start.to_string(),
+                end.to_string(),
+                symbols.iter().map(|symbol| symbol.to_string()).collect(),
+            )
+        };
+        assert_eq!(
+            Graph::from(DFA::try_from(dfa_txt).unwrap()).edges(),
+            vec![
+                edge("q1", "q1", &["b"]),
+                edge("q1", "q2", &["a"]),
+                edge("q2", "q2", &["a", "b"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn nfa_to_graph() {
+        let nfa_txt = fs::read_to_string("test.nfa").unwrap();
+        assert_eq!(
+            Graph::from(&NFA::try_from(nfa_txt).unwrap()).to_string(),
+            "q0\n    q0: a, b\n    q1: a\nq1\n    q2: a, b\nq2\n    q3: ε\nq3"
+        );
+    }
+
+    #[test]
+    fn collapses_ranges() {
+        let symbols = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(label(&symbols(&["c", "a", "e", "b", "d"])), "[a-e]");
+        assert_eq!(label(&symbols(&["a", "b", "c", "x", "z"])), "[a-c], x, z");
+        assert_eq!(label(&symbols(&["a", "b", "d"])), "a, b, d");
+        assert_eq!(label(&symbols(&["a", "ab", "b", "c"])), "[a-c], ab");
+        assert_eq!(label(&symbols(&["0", "1", "2", "ab", "z"])), "[0-2], z, ab");
+        assert_eq!(label(&symbols(&["ε"])), "ε");
+        assert_eq!(label(&[]), "");
+    }
 }
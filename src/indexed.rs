@@ -0,0 +1,223 @@
+use crate::dfa::DFA;
+use std::collections::HashMap;
+
+/// A total DFA with states and symbols interned to indices, for work that
+/// would otherwise hash a `String` on every step. Convert with
+/// `DFA::to_indexed`; the string-keyed `DFA` stays the format for I/O.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedDFA {
+    /// Name of each state id.
+    pub states: Vec<String>,
+    /// Symbol of each symbol id.
+    pub alphabet: Vec<String>,
+    /// `transition[state][symbol]`, defined for every pair.
+    pub transition: Vec<Vec<usize>>,
+    pub starting_state: usize,
+    /// Whether each state id is accepting.
+    pub accepting: Vec<bool>,
+}
+
+impl DFA {
+    /// Interns states and symbols in declared order. A partial DFA is made
+    /// total first with `complete`, which may add a dead state at the end.
+    pub fn to_indexed(&self) -> IndexedDFA {
+        let dfa = self.complete();
+        let index: HashMap<&str, usize> = dfa
+            .states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (state.as_str(), i))
+            .collect();
+        IndexedDFA {
+            transition: dfa
+                .states
+                .iter()
+                .map(|state| {
+                    dfa.alphabet
+                        .iter()
+                        .map(|symbol| index[dfa.step(state, symbol).unwrap()])
+                        .collect()
+                })
+                .collect(),
+            starting_state: index[dfa.starting_state.as_str()],
+            accepting: dfa
+                .states
+                .iter()
+                .map(|state| dfa.is_accepting(state))
+                .collect(),
+            states: dfa.states.clone(),
+            alphabet: dfa.alphabet.clone(),
+        }
+    }
+
+    /// Number of words of exactly `length` symbols the DFA accepts,
+    /// saturating at `u128::MAX`.
+    pub fn count_accepted(&self, length: usize) -> u128 {
+        self.to_indexed().count_accepted(length)
+    }
+
+    /// For each state, the number of words of exactly `length` symbols that
+    /// lead from it to an accepting state. This is `count_accepted` for every
+    /// starting state at once: the vector of accepting states is multiplied
+    /// `length` times by the matrix counting the symbols between each pair of
+    /// states. Counts saturate at `u64::MAX`.
+    pub fn path_counts(&self, length: usize) -> HashMap<String, u64> {
+        let indexed = self.to_indexed();
+        let n = indexed.states.len();
+        let mut matrix = vec![vec![0u64; n]; n];
+        for (i, row) in indexed.transition.iter().enumerate() {
+            for &j in row {
+                matrix[i][j] += 1;
+            }
+        }
+        let mut counts: Vec<u64> = indexed.accepting.iter().map(|&a| a as u64).collect();
+        for _ in 0..length {
+            counts = matrix
+                .iter()
+                .map(|row| {
+                    row.iter().zip(&counts).fold(0u64, |total, (&m, &count)| {
+                        total.saturating_add(m.saturating_mul(count))
+                    })
+                })
+                .collect();
+        }
+        // `to_indexed` keeps the declared states first, followed by any dead
+        // state it added.
+        self.states.iter().cloned().zip(counts).collect()
+    }
+}
+
+impl IndexedDFA {
+    /// The ids of the symbols of `word`, or `None` if one is not in the
+    /// alphabet.
+    pub fn encode(&self, word: &[&str]) -> Option<Vec<usize>> {
+        word.iter()
+            .map(|symbol| self.alphabet.iter().position(|s| s == symbol))
+            .collect()
+    }
+
+    pub fn accepts(&self, word: &[usize]) -> bool {
+        let end = word.iter().fold(self.starting_state, |state, &symbol| {
+            self.transition[state][symbol]
+        });
+        self.accepting[end]
+    }
+
+    /// Number of accepted words of exactly `length` symbols, counting the
+    /// paths of that length from the start into each state one step at a
+    /// time. Saturates at `u128::MAX`.
+    pub fn count_accepted(&self, length: usize) -> u128 {
+        let mut paths = vec![0u128; self.states.len()];
+        paths[self.starting_state] = 1;
+        for _ in 0..length {
+            let mut next = vec![0u128; self.states.len()];
+            for (state, &count) in paths.iter().enumerate() {
+                for &end in &self.transition[state] {
+                    next[end] = next[end].saturating_add(count);
+                }
+            }
+            paths = next;
+        }
+        paths
+            .iter()
+            .zip(&self.accepting)
+            .filter(|(_, &accepting)| accepting)
+            .fold(0u128, |total, (&count, _)| total.saturating_add(count))
+    }
+}
+
+#[cfg(test)]
+mod indexed_tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn agrees_with_dfa() {
+        let dfa = DFA::random(30, &["a", "b", "c"], 5);
+        let indexed = dfa.to_indexed();
+        let mut word = vec![];
+        for i in 0..200 {
+            word.push(["a", "b", "c"][(i * 7 + i / 3) % 3]);
+            let encoded = indexed.encode(&word).unwrap();
+            assert_eq!(indexed.accepts(&encoded), dfa.accepts(&word), "{word:?}");
+        }
+        assert_eq!(indexed.encode(&["a", "z"]), None);
+    }
+
+    #[test]
+    fn partial_gets_dead_state() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        let indexed = dfa.to_indexed();
+        assert_eq!(indexed.states.len(), dfa.states.len() + 1);
+        assert!(indexed.transition.iter().all(|row| row.len() == 2));
+        let b = indexed.encode(&["b"]).unwrap();
+        assert_eq!(indexed.accepts(&b), dfa.accepts(&["b"]));
+    }
+
+    #[test]
+    fn counts_words() {
+        // Words over {a, b} of length 6 with a number of symbols divisible by
+        // 3: all of them. Containing `bb`: everything but the 21 without.
+        let dfa = DFA::modulo_length(&["a", "b"], 3);
+        assert_eq!(dfa.count_accepted(6), 64);
+        assert_eq!(dfa.count_accepted(5), 0);
+        let dfa = DFA::contains_substring(&["a", "b"], &["b", "b"]);
+        assert_eq!(dfa.count_accepted(6), 64 - 21);
+        assert_eq!(dfa.count_accepted(0), 0);
+    }
+
+    // Words of `length` symbols accepted from `state`, by trying them all.
+    fn brute_force(dfa: &DFA, state: &str, length: usize) -> u64 {
+        let mut words: Vec<Vec<&str>> = vec![vec![]];
+        for _ in 0..length {
+            words = words
+                .iter()
+                .flat_map(|word| {
+                    dfa.alphabet.iter().map(move |symbol| {
+                        let mut word = word.clone();
+                        word.push(symbol.as_str());
+                        word
+                    })
+                })
+                .collect();
+        }
+        let mut from = dfa.clone();
+        from.starting_state = state.to_string();
+        words.iter().filter(|word| from.accepts(word)).count() as u64
+    }
+
+    #[test]
+    fn path_counts_match_enumeration() {
+        let partial = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        for dfa in [partial, DFA::random(6, &["a", "b", "c"], 4)] {
+            for length in 0..6 {
+                let counts = dfa.path_counts(length);
+                assert_eq!(counts.len(), dfa.states.len());
+                for state in &dfa.states {
+                    assert_eq!(
+                        counts[state],
+                        brute_force(&dfa, state, length),
+                        "{state} {length}"
+                    );
+                }
+                assert_eq!(
+                    counts[&dfa.starting_state] as u128,
+                    dfa.count_accepted(length)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn path_counts_saturate() {
+        let dfa = DFA::modulo_length(&["a", "b", "c", "d"], 1);
+        assert_eq!(dfa.path_counts(40)["q0"], u64::MAX);
+    }
+
+    #[test]
+    fn count_saturates() {
+        let dfa = DFA::modulo_length(&["a", "b", "c", "d"], 1);
+        assert_eq!(dfa.count_accepted(100), u128::MAX);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::graph::Graph;
+use std::{
+    collections::HashMap,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+
+/// A position or direction on the layout plane, so the layout does not tie
+/// the library to a graphics crate.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Point {
+        Point { x, y }
+    }
+
+    pub fn zero() -> Point {
+        Point::default()
+    }
+
+    pub fn length_sqr(&self) -> f32 {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length_sqr().sqrt()
+    }
+
+    pub fn distance_to(&self, other: Point) -> f32 {
+        (*self - other).length()
+    }
+
+    pub fn scale_by(&self, factor: f32) -> Point {
+        Point::new(self.x * factor, self.y * factor)
+    }
+
+    /// The same direction with length 1, or zero for the zero vector.
+    pub fn normalized(&self) -> Point {
+        let length = self.length();
+        if length == 0.0 {
+            *self
+        } else {
+            self.scale_by(1.0 / length)
+        }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other: Point) {
+        *self = *self - other;
+    }
+}
+
+/// Constants of the force-directed layout run by `step`.
+#[derive(Debug, Clone, Copy)]
+pub struct PhysicsParams {
+    /// Strength of the logarithmic spring between connected nodes. Higher
+    /// values pull neighbours together harder.
+    pub attraction: f32,
+    /// Strength of the push between every pair of nodes, falling off with
+    /// distance. Raise it to spread out dense graphs.
+    pub repulsion: f32,
+    /// Length at which a spring exerts no force. Connected nodes closer than
+    /// this are pushed apart instead.
+    pub rest_length: f32,
+    /// Fraction of velocity kept each step. Lower values settle faster but
+    /// can freeze the layout before it untangles.
+    pub damping: f32,
+    /// Strength of the pull towards each node's anchor, which keeps the
+    /// graph on screen.
+    pub centering: f32,
+}
+
+impl Default for PhysicsParams {
+    fn default() -> Self {
+        PhysicsParams {
+            attraction: 0.1,
+            repulsion: 100.0,
+            rest_length: 0.2,
+            damping: 0.97,
+            centering: 0.01,
+        }
+    }
+}
+
+impl PhysicsParams {
+    pub const NAMES: [&'static str; 5] = [
+        "attraction",
+        "repulsion",
+        "rest length",
+        "damping",
+        "centering",
+    ];
+
+    pub fn get(&self, index: usize) -> f32 {
+        [
+            self.attraction,
+            self.repulsion,
+            self.rest_length,
+            self.damping,
+            self.centering,
+        ][index]
+    }
+
+    /// Scales the selected parameter by 10%, except damping which moves in
+    /// small steps since anything at or above 1.0 never settles.
+    pub fn adjust(&mut self, index: usize, increase: bool) {
+        let factor = if increase { 1.1 } else { 1.0 / 1.1 };
+        match index {
+            0 => self.attraction *= factor,
+            1 => self.repulsion *= factor,
+            2 => self.rest_length *= factor,
+            3 => {
+                let step = if increase { 0.01 } else { -0.01 };
+                self.damping = (self.damping + step).clamp(0.0, 0.99);
+            }
+            _ => self.centering *= factor,
+        }
+    }
+}
+
+/// A node as the simulation sees it.
+#[derive(Debug, Clone, Copy)]
+pub struct Body {
+    pub position: Point,
+    pub velocity: Point,
+    /// Held in place; the simulation leaves it alone.
+    pub pinned: bool,
+}
+
+/// Advances the simulation by one step. `adjacent[i][j]` says whether nodes
+/// `i` and `j` are connected, and each body is pulled towards
+/// `anchors[i].0` with strength `anchors[i].1`. Returns the kinetic energy
+/// afterwards, which callers can use to tell when the layout has settled.
+pub fn step(
+    bodies: &mut [Body],
+    adjacent: &[Vec<bool>],
+    anchors: &[(Point, f32)],
+    params: &PhysicsParams,
+) -> f32 {
+    let mut acceleration: Vec<Point> = bodies
+        .iter()
+        .zip(anchors)
+        .map(|(body, (anchor, strength))| {
+            let x1 = *anchor - body.position;
+            x1.normalized().scale_by(strength * x1.length())
+        })
+        .collect();
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let dir = bodies[j].position - bodies[i].position;
+            if adjacent[i][j] {
+                let spring = params.attraction * (dir.length() / params.rest_length).ln();
+                acceleration[i] += dir.normalized().scale_by(spring);
+                acceleration[j] -= dir.normalized().scale_by(spring);
+            }
+            let repulsive_force = dir.scale_by(params.repulsion / dir.length_sqr());
+            acceleration[i] -= repulsive_force;
+            acceleration[j] += repulsive_force;
+        }
+    }
+    for (body, acceleration) in bodies.iter_mut().zip(acceleration) {
+        if body.pinned {
+            body.velocity = Point::zero();
+            continue;
+        }
+        body.velocity += acceleration.scale_by(0.1);
+        body.velocity = body.velocity.scale_by(params.damping);
+        body.position += body.velocity;
+    }
+    bodies.iter().map(|body| body.velocity.length_sqr()).sum()
+}
+
+/// Centers of the cells of the most square grid with `count` cells covering a
+/// `w` x `h` area. A single cell is centered in the area.
+pub fn grid_centers(count: usize, w: f32, h: f32) -> Vec<Point> {
+    let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+    let rows = count.div_ceil(columns).max(1);
+    let (cell_w, cell_h) = (w / columns as f32, h / rows as f32);
+    (0..count)
+        .map(|i| {
+            Point::new(
+                ((i % columns) as f32 + 0.5) * cell_w,
+                ((i / columns) as f32 + 0.5) * cell_h,
+            )
+        })
+        .collect()
+}
+
+// Side of the square each connected component is given by `force_layout`.
+const CELL: f32 = 400.0;
+
+/// Lays out `graph` without a window by running `iterations` steps of the
+/// simulation. Each weakly connected component gets its own cell of a grid
+/// and starts on a small circle around the cell's center, so the result is
+/// the same on every run.
+pub fn force_layout(
+    graph: &Graph,
+    iterations: usize,
+    params: &PhysicsParams,
+) -> HashMap<String, Point> {
+    let n = graph.nodes.len();
+    let index: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.as_str(), i))
+        .collect();
+    let mut adjacent = vec![vec![false; n]; n];
+    for (start, end) in graph.adj_mat.keys() {
+        if let (Some(&i), Some(&j)) = (index.get(start.as_str()), index.get(end.as_str())) {
+            adjacent[i][j] = true;
+            adjacent[j][i] = true;
+        }
+    }
+
+    let components = graph.weakly_connected_components();
+    let mut component = vec![0; n];
+    for (c, members) in components.iter().enumerate() {
+        for member in members {
+            component[index[member.as_str()]] = c;
+        }
+    }
+    let count = components.len();
+    let side = CELL * (count as f32).sqrt().ceil();
+    let centers = grid_centers(count, side, side);
+    let anchors: Vec<(Point, f32)> = component
+        .iter()
+        .map(|&c| (centers[c], params.centering))
+        .collect();
+    let mut bodies: Vec<Body> = anchors
+        .iter()
+        .enumerate()
+        .map(|(i, (center, _))| {
+            // Golden angle steps spread the nodes evenly around the circle.
+            let angle = i as f32 * 2.4;
+            Body {
+                position: *center + Point::new(angle.cos(), angle.sin()).scale_by(50.0),
+                velocity: Point::zero(),
+                pinned: false,
+            }
+        })
+        .collect();
+
+    for _ in 0..iterations {
+        step(&mut bodies, &adjacent, &anchors, params);
+    }
+    graph
+        .nodes
+        .iter()
+        .cloned()
+        .zip(bodies.iter().map(|body| body.position))
+        .collect()
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+    use crate::dfa::DFA;
+
+    fn two_pairs() -> Graph {
+        Graph::from(
+            DFA::try_from(
+                "states = [q0, q1, q2, q3]
+alphabet = [a]
+starting_state = q0
+accepting_states = []
+transitions =
+    q0,a = q1;
+    q1,a = q0;
+    q2,a = q3;
+    q3,a = q2;"
+                    .to_string(),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn separates_components() {
+        let layout = force_layout(&two_pairs(), 500, &PhysicsParams::default());
+        let distance = |a: &str, b: &str| layout[a].distance_to(layout[b]);
+        assert!(distance("q0", "q2") > 2.0 * distance("q0", "q1"));
+        assert!(distance("q1", "q3") > 2.0 * distance("q2", "q3"));
+    }
+
+    #[test]
+    fn deterministic() {
+        let params = PhysicsParams::default();
+        let first = force_layout(&two_pairs(), 50, &params);
+        let second = force_layout(&two_pairs(), 50, &params);
+        for (node, position) in first {
+            assert_eq!(second[&node], position);
+        }
+    }
+
+    #[test]
+    fn point_arithmetic() {
+        let point = Point::new(3.0, 4.0);
+        assert_eq!(point.length(), 5.0);
+        assert_eq!(point.normalized(), Point::new(0.6, 0.8));
+        assert_eq!(point - point, Point::zero());
+        assert_eq!(Point::zero().normalized(), Point::zero());
+    }
+
+    #[test]
+    fn pinned_bodies_stay() {
+        let mut bodies = [
+            Body {
+                position: Point::new(0.0, 0.0),
+                velocity: Point::zero(),
+                pinned: true,
+            },
+            Body {
+                position: Point::new(1.0, 0.0),
+                velocity: Point::zero(),
+                pinned: false,
+            },
+        ];
+        let adjacent = vec![vec![false; 2]; 2];
+        let anchors = [(Point::zero(), 0.0); 2];
+        for _ in 0..10 {
+            step(&mut bodies, &adjacent, &anchors, &PhysicsParams::default());
+        }
+        assert_eq!(bodies[0].position, Point::zero());
+        assert!(bodies[1].position.x > 1.0);
+    }
+}
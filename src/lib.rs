@@ -0,0 +1,9 @@
+pub mod binary;
+pub mod builder;
+pub mod dfa;
+pub mod graph;
+pub mod indexed;
+pub mod layout;
+pub mod nfa;
+pub mod regex;
+pub mod svg;
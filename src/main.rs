@@ -1,26 +1,602 @@
 #![feature(iter_intersperse)]
-use std::fs;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    io::{self, Read},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 
-use dfa::DFA;
-use graph::Graph;
+use automata::{
+    dfa::DFA,
+    graph::{self, Graph},
+    layout::{self, grid_centers, Body, PhysicsParams, Point},
+    nfa::NFA,
+};
 use raylib::{misc::get_random_value, prelude::*};
-mod dfa;
-mod graph;
 
 #[derive(Debug, Clone)]
 struct DisplayNodeElement {
     label: String,
     position: Vector2,
     velocity: Vector2,
-    acceleration: Vector2,
     size: f32,
     color: Color,
+    // Index of the weakly connected component, into `DrawableGraph::centers`.
+    component: usize,
+    // Held in place by the user; the physics leaves it alone.
+    pinned: bool,
 }
 
 #[derive(Debug)]
 struct DrawableGraph {
+    // The machine being shown, kept so edits can be applied to it.
+    dfa: DFA,
     graph: Graph,
     positions: Vec<DisplayNodeElement>,
+    // Every edge as `(from, to, symbols)` between node indices, in node order.
+    edges: Vec<(usize, usize, Vec<String>)>,
+    // `adjacent[i][j]` if there is an edge between nodes `i` and `j` in either
+    // direction, so the physics loop does not have to probe `adj_mat`.
+    adjacent: Vec<Vec<bool>>,
+    // BFS distance of each node from the starting state, `None` if unreachable.
+    distances: Vec<Option<usize>>,
+    // Symbols each node has no transition on.
+    missing: Vec<Vec<String>>,
+    // Index of the group of equivalent states each node could be merged
+    // with, `None` if it has no equivalent.
+    redundant: Vec<Option<usize>>,
+    // Index of the strongly connected component with a cycle each node is
+    // in, `None` for nodes no cycle passes through.
+    loops: Vec<Option<usize>>,
+    // Whether an accepting state is reachable from each node.
+    live: Vec<bool>,
+    // Whether each node is a trap state, which the view can dim or hide.
+    trap: Vec<bool>,
+    // Edges `(from, to)` between node indices along a cycle found on load.
+    cycle: HashSet<(usize, usize)>,
+    // Point each weakly connected component is pulled towards.
+    centers: Vec<Vector2>,
+    // Per-node positions of the leveled layout; while set, each node is pulled
+    // towards its own target instead of its component center.
+    targets: Option<Vec<Vector2>>,
+    // Whether node sizes grow with in-degree instead of being uniform.
+    sized_by_degree: bool,
+    params: PhysicsParams,
+    // Set once the layout has stopped moving; `update_graph` then skips the
+    // simulation until `unsettle` is called.
+    settled: bool,
+    calm_frames: usize,
+}
+
+impl DrawableGraph {
+    fn new(dfa: DFA, w: i32, h: i32) -> DrawableGraph {
+        let graph = Graph::from(dfa.clone());
+        DrawableGraph::with_graph(dfa, graph, w, h)
+    }
+
+    // Draws the NFA itself while `dfa` holds its subset construction, which
+    // is what runs inputs and gets saved.
+    fn from_nfa(nfa: &NFA, w: i32, h: i32) -> DrawableGraph {
+        DrawableGraph::with_graph(nfa.to_dfa(), Graph::from(nfa), w, h)
+    }
+
+    // Places the nodes randomly in the middle third of a `w` x `h` window.
+    // The analyses of `dfa` are matched to the nodes of `graph` by label, so
+    // they come out empty when `graph` shows a different machine.
+    fn with_graph(dfa: DFA, graph: Graph, w: i32, h: i32) -> DrawableGraph {
+        let distances = dfa.distances();
+        let live = dfa.live_states();
+        let traps = dfa.trap_states();
+        let cycle = dfa.find_cycle().unwrap_or_default();
+        let groups = dfa.redundant_state_groups();
+        let loops: Vec<Vec<String>> = dfa
+            .strongly_connected_components()
+            .into_iter()
+            .filter(|component| {
+                let state = component[0].as_str();
+                component.len() > 1
+                    || dfa
+                        .alphabet
+                        .iter()
+                        .any(|symbol| dfa.step(state, symbol) == Some(state))
+            })
+            .collect();
+        let components = dfa.weakly_connected_components();
+        let component_of = |state: &String| {
+            components
+                .iter()
+                .position(|component| component.contains(state))
+                .unwrap_or(0)
+        };
+        let mut missing: HashMap<String, Vec<String>> = HashMap::new();
+        for (state, symbol) in dfa.missing_transitions() {
+            missing.entry(state).or_default().push(symbol);
+        }
+        let positions = graph
+            .nodes
+            .iter()
+            .map(|node| DisplayNodeElement {
+                position: Vector2 {
+                    x: f64::from(get_random_value::<i32>(w / 3, 2 * w / 3)) as f32,
+                    y: f64::from(get_random_value::<i32>(h / 3, 2 * h / 3)) as f32,
+                },
+                label: node.clone(),
+                velocity: Vector2 { x: 0.0, y: 0.0 },
+                pinned: false,
+                size: NODE_SIZE,
+                // The declared sink is drawn dimmed, as it only rejects.
+                color: if dfa.dead_state.as_ref() == Some(node) {
+                    Color::LIGHTGRAY
+                } else {
+                    Color::RED
+                },
+                component: component_of(node),
+            })
+            .collect();
+        let distances = graph
+            .nodes
+            .iter()
+            .map(|node| distances.get(node).copied())
+            .collect();
+        let missing = graph
+            .nodes
+            .iter()
+            .map(|node| missing.remove(node).unwrap_or_default())
+            .collect();
+        let redundant = graph
+            .nodes
+            .iter()
+            .map(|node| groups.iter().position(|group| group.contains(node)))
+            .collect();
+        let loops = graph
+            .nodes
+            .iter()
+            .map(|node| loops.iter().position(|component| component.contains(node)))
+            .collect();
+        let live = graph.nodes.iter().map(|node| live.contains(node)).collect();
+        let trap = graph
+            .nodes
+            .iter()
+            .map(|node| traps.contains(node))
+            .collect();
+        let node_index: HashMap<&String, usize> = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node, i))
+            .collect();
+        let index = |state: &String| node_index.get(state).copied();
+        let mut edges: Vec<(usize, usize, Vec<String>)> = graph
+            .edges()
+            .into_iter()
+            .filter_map(|(from, to, symbols)| Some((index(&from)?, index(&to)?, symbols)))
+            .collect();
+        edges.sort();
+        let mut adjacent = vec![vec![false; graph.nodes.len()]; graph.nodes.len()];
+        for &(i, j, _) in &edges {
+            adjacent[i][j] = true;
+            adjacent[j][i] = true;
+        }
+        let cycle = cycle
+            .iter()
+            .zip(cycle.iter().cycle().skip(1))
+            .filter_map(|(from, to)| Some((index(from)?, index(to)?)))
+            .collect();
+
+        DrawableGraph {
+            dfa,
+            graph,
+            positions,
+            edges,
+            adjacent,
+            distances,
+            missing,
+            redundant,
+            loops,
+            live,
+            trap,
+            cycle,
+            centers: grid_centers(components.len(), w as f32, h as f32)
+                .into_iter()
+                .map(to_vector)
+                .collect(),
+            targets: None,
+            sized_by_degree: false,
+            params: PhysicsParams::default(),
+            settled: false,
+            calm_frames: 0,
+        }
+    }
+
+    // Textbook layout: one column per BFS level from the starting state, left
+    // to right, with unreachable nodes in a final column. Nodes start at their
+    // targets and the physics only relaxes them from there.
+    fn level_layout(&mut self, w: i32, h: i32) {
+        let last = self
+            .distances
+            .iter()
+            .flatten()
+            .max()
+            .map_or(0, |max| max + 1);
+        let levels: Vec<usize> = self
+            .distances
+            .iter()
+            .map(|distance| distance.unwrap_or(last))
+            .collect();
+        let columns = levels.iter().max().map_or(1, |max| max + 1);
+
+        let margin = 60.0;
+        let column_width = (w as f32 - 2.0 * margin) / (columns.max(2) - 1) as f32;
+        let mut targets = vec![Vector2::zero(); levels.len()];
+        for level in 0..columns {
+            let members: Vec<usize> = (0..levels.len()).filter(|&i| levels[i] == level).collect();
+            let row_height = (h as f32 - 2.0 * margin) / members.len().max(1) as f32;
+            for (row, &i) in members.iter().enumerate() {
+                targets[i] = Vector2::new(
+                    margin + level as f32 * column_width,
+                    margin + (row as f32 + 0.5) * row_height,
+                );
+            }
+        }
+
+        for (node, target) in self.positions.iter_mut().zip(&targets) {
+            node.position = *target;
+            node.velocity = Vector2::zero();
+        }
+        self.targets = Some(targets);
+        self.unsettle();
+    }
+
+    // Replaces the machine with an edited version. Nodes that survive the
+    // edit keep their place; new ones are scattered like on load.
+    fn edit(&mut self, dfa: DFA, w: i32, h: i32) {
+        let mut next = DrawableGraph::new(dfa, w, h);
+        for node in &mut next.positions {
+            if let Some(old) = self.positions.iter().find(|old| old.label == node.label) {
+                node.position = old.position;
+                node.velocity = old.velocity;
+                node.pinned = old.pinned;
+            }
+        }
+        next.params = self.params;
+        next.size_by_degree(self.sized_by_degree);
+        if self.targets.is_some() {
+            next.level_layout(w, h);
+        }
+        *self = next;
+    }
+
+    // Sizes nodes by in-degree, clamped to `NODE_SIZE_RANGE`, so states many
+    // edges lead into stand out; otherwise every node gets `NODE_SIZE`.
+    fn size_by_degree(&mut self, enabled: bool) {
+        self.sized_by_degree = enabled;
+        let degrees = self.graph.degrees();
+        let (min, max) = NODE_SIZE_RANGE;
+        for node in &mut self.positions {
+            node.size = if enabled {
+                (min + 6.0 * degrees[&node.label].0 as f32).min(max)
+            } else {
+                NODE_SIZE
+            };
+        }
+        self.unsettle();
+    }
+
+    fn unsettle(&mut self) {
+        self.settled = false;
+        self.calm_frames = 0;
+    }
+
+    // Whether node `i` is left out of the drawing, and so cannot be hovered,
+    // clicked or found by the search either.
+    fn hidden(&self, i: usize, traps: Traps) -> bool {
+        traps == Traps::Hidden && self.trap[i]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColorMode {
+    Plain,
+    // Gradient from the starting state to the farthest reachable state.
+    Distance,
+    // One hue per group of states that could be merged, the rest gray.
+    Redundant,
+    // One hue per strongly connected component that can loop, the rest gray.
+    Loops,
+    // Tints the states reachable from the selected one and the states that
+    // can still reach an accepting state.
+    Reach,
+}
+
+impl ColorMode {
+    fn next(self) -> ColorMode {
+        match self {
+            ColorMode::Plain => ColorMode::Distance,
+            ColorMode::Distance => ColorMode::Redundant,
+            ColorMode::Redundant => ColorMode::Loops,
+            ColorMode::Loops => ColorMode::Reach,
+            ColorMode::Reach => ColorMode::Plain,
+        }
+    }
+}
+
+// How the layout keeps nodes on screen, cycled with `G`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Centering {
+    // Pulled towards the center of their component.
+    Attract,
+    // Left to the other forces, so repulsion spreads them freely.
+    Free,
+    // Pushed back whenever they leave the window.
+    Confine,
+}
+
+impl Centering {
+    fn next(self) -> Centering {
+        match self {
+            Centering::Attract => Centering::Free,
+            Centering::Free => Centering::Confine,
+            Centering::Confine => Centering::Attract,
+        }
+    }
+}
+
+// How trap states and the edges into them are drawn, cycled with `T`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Traps {
+    Shown,
+    // Faded, so the rest of the machine stands out.
+    Dimmed,
+    // Left out entirely, as textbook drawings of partial DFAs do.
+    Hidden,
+}
+
+impl Traps {
+    fn next(self) -> Traps {
+        match self {
+            Traps::Shown => Traps::Dimmed,
+            Traps::Dimmed => Traps::Hidden,
+            Traps::Hidden => Traps::Shown,
+        }
+    }
+}
+
+// Display settings toggled from the keyboard.
+#[derive(Debug)]
+struct View {
+    color_mode: ColorMode,
+    // Draw the transitions a partial DFA is missing as edges into a sink.
+    show_missing: bool,
+    show_cycle: bool,
+    // Color and thicken edges by the share of the alphabet they carry.
+    edge_frequency: bool,
+    // Draw the loaded NFA rather than its subset construction.
+    show_nfa: bool,
+    // Overlay listing the key bindings and what the colors mean.
+    show_help: bool,
+    // Draw one offset edge per symbol instead of one labelled with them all.
+    parallel_edges: bool,
+    // Drop the arrowheads and draw reciprocal edges as a single line.
+    undirected: bool,
+    centering: Centering,
+    // Kept when another file is loaded, like the rest of the view.
+    traps: Traps,
+    // Layout steps run each frame, from 1 to `MAX_STEPS_PER_FRAME`.
+    steps_per_frame: usize,
+}
+
+// Label filter typed after pressing `/`.
+#[derive(Debug, Default)]
+struct Search {
+    query: String,
+    // Which of the current matches Enter/Tab last focused.
+    cursor: usize,
+}
+
+// Symbol being typed for a new transition between two clicked nodes.
+#[derive(Debug)]
+struct TransitionPrompt {
+    from: String,
+    to: String,
+    symbol: String,
+}
+
+// An input word being stepped through one symbol at a time.
+#[derive(Debug)]
+struct Execution {
+    input: Vec<String>,
+    // Number of symbols consumed so far.
+    position: usize,
+    // Frames left before the edge taken on each symbol fades from the trail.
+    trail: Vec<usize>,
+}
+
+impl Execution {
+    // Symbols are separated by spaces or commas if there are any, otherwise
+    // every character is one symbol.
+    fn new(text: &str) -> Execution {
+        let input: Vec<String> = if text.contains([' ', ',']) {
+            text.split([' ', ','])
+                .filter(|symbol| !symbol.is_empty())
+                .map(str::to_string)
+                .collect()
+        } else {
+            text.chars().map(String::from).collect()
+        };
+        let trail = vec![0; input.len()];
+        Execution {
+            input,
+            position: 0,
+            trail,
+        }
+    }
+
+    // States visited while consuming the input, stopping early if the DFA
+    // has no transition for the next symbol.
+    fn trace<'a>(&self, dfa: &'a DFA) -> Vec<&'a str> {
+        let mut trace = vec![dfa.starting_state.as_str()];
+        for symbol in &self.input {
+            match dfa.step(trace.last().unwrap(), symbol) {
+                Some(next) => trace.push(next),
+                None => break,
+            }
+        }
+        trace
+    }
+
+    // Whether the whole input is read and ends in an accepting state.
+    fn accepted(&self, dfa: &DFA) -> bool {
+        let trace = self.trace(dfa);
+        trace.len() > self.input.len() && dfa.is_accepting(trace[self.input.len()])
+    }
+
+    fn fade(&mut self) {
+        for frames in &mut self.trail {
+            *frames = frames.saturating_sub(1);
+        }
+    }
+
+    // Steps forward or back, never past the end of the input or more than
+    // one symbol beyond where the DFA got stuck.
+    fn advance(&mut self, dfa: &DFA, forward: bool) {
+        if forward {
+            let end = self.input.len().min(self.trace(dfa).len());
+            let next = (self.position + 1).min(end);
+            // Stepping onto a transition that exists leaves it on the trail.
+            if next > self.position && next < self.trace(dfa).len() {
+                self.trail[self.position] = TRAIL_FRAMES;
+            }
+            self.position = next;
+        } else {
+            self.position = self.position.saturating_sub(1);
+        }
+    }
+}
+
+// Animation of `M`: each node slides into the node it is merged with in the
+// minimal DFA, or shrinks away if it has none, before the view is replaced.
+#[derive(Debug)]
+struct Merge {
+    minimal: DFA,
+    into: Vec<Option<usize>>,
+    frames_left: usize,
+}
+
+impl Merge {
+    fn new(graph: &DrawableGraph) -> Merge {
+        let minimal = graph.dfa.minimize();
+        let targets = merge_targets(&graph.dfa, &minimal);
+        let into = graph
+            .positions
+            .iter()
+            .map(|node| {
+                let target = targets.get(&node.label)?;
+                graph
+                    .positions
+                    .iter()
+                    .position(|node| &node.label == target)
+            })
+            .collect();
+        Merge {
+            minimal,
+            into,
+            frames_left: MERGE_FRAMES,
+        }
+    }
+
+    // Advances the animation by a frame. Returns true once it has finished.
+    fn step(&mut self, graph: &mut DrawableGraph) -> bool {
+        for i in 0..graph.positions.len() {
+            let target = self.into[i].map(|j| graph.positions[j].position);
+            let node = &mut graph.positions[i];
+            node.velocity = Vector2::zero();
+            match target {
+                Some(target) => node.position = node.position.lerp(target, 0.15),
+                None => node.size *= 0.9,
+            }
+        }
+        self.frames_left -= 1;
+        self.frames_left == 0
+    }
+}
+
+// Where each state of `dfa` ends up in `minimal`: the state its shortest
+// access word leads to there. Unreachable states, and those equivalent to the
+// dead state `minimize` drops, are left out.
+fn merge_targets(dfa: &DFA, minimal: &DFA) -> HashMap<String, String> {
+    let mut words = HashMap::from([(dfa.starting_state.clone(), vec![])]);
+    let mut queue = VecDeque::from([dfa.starting_state.clone()]);
+    while let Some(state) = queue.pop_front() {
+        for (symbol, next) in dfa.outgoing(&state) {
+            if !words.contains_key(next) {
+                let mut word: Vec<&str> = words[&state].clone();
+                word.push(symbol);
+                words.insert(next.to_string(), word);
+                queue.push_back(next.to_string());
+            }
+        }
+    }
+    words
+        .into_iter()
+        .filter_map(|(state, word)| Some((state, minimal.run(&word)?.pop()?)))
+        .collect()
+}
+
+// The layout counts as settled once the kinetic energy stays below
+// `SETTLE_ENERGY` for `SETTLE_FRAMES` consecutive frames.
+const SETTLE_ENERGY: f32 = 0.01;
+const SETTLE_FRAMES: usize = 30;
+
+// Radius of a node, and the range it is scaled within by in-degree.
+const NODE_SIZE: f32 = 30.0;
+const NODE_SIZE_RANGE: (f32, f32) = (18.0, 54.0);
+
+// Length of the minimization animation.
+const MERGE_FRAMES: usize = 45;
+
+// Frames an edge stays on the trail of an execution after being taken.
+const TRAIL_FRAMES: usize = 90;
+
+// Fraction of how far a node is outside the window added to its velocity
+// each frame in `Centering::Confine`.
+const CONFINE_STRENGTH: f32 = 0.1;
+
+// Opacity of trap states while they are dimmed.
+const TRAP_FADE: f32 = 0.25;
+
+// Fastest the layout can be run with `+`, in steps per frame.
+const MAX_STEPS_PER_FRAME: usize = 20;
+
+// Layout steps timed on startup under `--verbose`.
+const TIMED_LAYOUT_STEPS: usize = 100;
+
+// Distance in pixels an arrow key moves the selected node.
+const NUDGE: f32 = 10.0;
+
+// Tints of `ColorMode::Reach` for states reachable from the selected one
+// and states that can still accept; states that are both get a mix.
+const REACHED: Color = Color::SKYBLUE;
+const LIVE: Color = Color::GREEN;
+
+// Length of an arrowhead as a fraction of the size of the node it points at,
+// and the length it is never shrunk below on short edges.
+const ARROW_FRACTION: f32 = 0.5;
+const MIN_ARROW: f32 = 6.0;
+
+// Sideways distance between the edges drawn for each symbol (`B`).
+const PARALLEL_GAP: f32 = 12.0;
+
+// The layout works in its own `Point` so the library does not depend on
+// raylib; these convert at the boundary.
+fn to_point(vector: Vector2) -> Point {
+    Point::new(vector.x, vector.y)
+}
+
+fn to_vector(point: Point) -> Vector2 {
+    Vector2::new(point.x, point.y)
 }
 
 // Function to rotate a point around another point
@@ -32,30 +608,46 @@ fn rotate_point(point: Vector2, pivot: Vector2, angle: f32) -> Vector2 {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = headless(&args) {
+        std::process::exit(code);
+    }
+
+    // Bad arguments are reported before a window opens.
+    let Args {
+        path,
+        seed,
+        verbose,
+    } = match parse_args(args.into_iter()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
     let (w, h) = (640, 480);
     let (mut rl, thread) = raylib::init().size(w, h).title("Hello, World").build();
 
     rl.set_target_fps(60);
 
-    let dfa_code = fs::read_to_string("big.dfa").expect("Failed to read 'test.dfa'");
-    let mut graph = DrawableGraph {
-        graph: Graph::from(DFA::try_from(dfa_code).unwrap()),
-        positions: vec![],
+    VERBOSE.store(verbose, Ordering::Relaxed);
+    // The same seed places the nodes the same way on every run.
+    if let Some(seed) = seed {
+        unsafe { raylib::ffi::SetRandomSeed(seed) };
+    }
+    let mut path = path.unwrap_or("big.dfa".to_string());
+    let (mut graph, mut nfa, mut machines) = match load(&path, w, h) {
+        Ok(loaded) => loaded,
+        Err(err) => match wait_for_drop(&mut rl, &thread, err, w, h) {
+            Some((dropped, loaded)) => {
+                path = dropped;
+                loaded
+            }
+            None => return,
+        },
     };
-
-    graph.graph.nodes.iter().for_each(|node| {
-        graph.positions.push(DisplayNodeElement {
-            position: Vector2 {
-                x: f64::from(get_random_value::<i32>(w / 3, 2 * w / 3)) as f32,
-                y: f64::from(get_random_value::<i32>(h / 3, 2 * h / 3)) as f32,
-            },
-            acceleration: Vector2::default(),
-            label: node.clone(),
-            velocity: Vector2 { x: 0.0, y: 0.0 },
-            size: 30.0,
-            color: Color::RED,
-        })
-    });
+    rl.set_window_title(&thread, graph.dfa.name.as_deref().unwrap_or(&path));
 
     // println!("{:#?}", graph);
     // for i in 0..graph.positions.len() {
@@ -64,101 +656,1379 @@ fn main() {
     //     }
     // }
 
+    let mut selected_param = 0;
+    let mut search: Option<Search> = None;
+    let mut selected: Option<usize> = None;
+    let mut prompt: Option<TransitionPrompt> = None;
+    // Word typed after pressing `I`, and the execution it starts.
+    let mut input_prompt: Option<String> = None;
+    let mut execution: Option<Execution> = None;
+    let mut merge: Option<Merge> = None;
+    // Result of the last edit, e.g. why it was rejected.
+    let mut status = String::new();
+    // Why the last dropped file could not be loaded, shown until a click.
+    let mut drop_error: Option<String> = None;
+    let mut view = View {
+        color_mode: ColorMode::Plain,
+        show_missing: false,
+        show_cycle: false,
+        edge_frequency: false,
+        show_nfa: nfa.is_some(),
+        show_help: false,
+        parallel_edges: false,
+        undirected: false,
+        centering: Centering::Attract,
+        traps: Traps::Shown,
+        steps_per_frame: 1,
+    };
+    let mut camera = Camera2D {
+        offset: Vector2::new(w as f32 / 2.0, h as f32 / 2.0),
+        target: Vector2::new(w as f32 / 2.0, h as f32 / 2.0),
+        rotation: 0.0,
+        zoom: 1.0,
+    };
+
+    // Which of `machines` is shown, for files holding several.
+    let mut machine = 0;
+    // Time spent in the first `TIMED_LAYOUT_STEPS` layout steps.
+    let (mut layout_time, mut layout_steps) = (Duration::ZERO, 0);
+
     while !rl.window_should_close() {
+        if let Some(dropped) = dropped_file(&mut rl) {
+            match load(&dropped, w, h) {
+                Ok(loaded) => {
+                    (graph, nfa, machines) = loaded;
+                    machine = 0;
+                    path = dropped;
+                    view.show_nfa = nfa.is_some();
+                    (selected, execution, merge) = (None, None, None);
+                    rl.set_window_title(&thread, graph.dfa.name.as_deref().unwrap_or(&path));
+                    status = format!("Loaded {path}");
+                    drop_error = None;
+                }
+                Err(err) => drop_error = Some(err),
+            }
+        }
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            drop_error = None;
+        }
+
+        let mouse = rl.get_mouse_position();
+        let world_mouse = rl.get_screen_to_world2D(mouse, camera);
+
+        // Input is ignored while the minimization plays.
+        if let Some(animation) = merge.as_mut() {
+            if animation.step(&mut graph) {
+                let before = graph.dfa.states.len();
+                let after = animation.minimal.states.len();
+                graph.edit(animation.minimal.clone(), w, h);
+                status = format!("Minimized {before} states to {after}");
+                merge = None;
+            }
+        } else if let Some(edit) = prompt.as_mut() {
+            if let Some(confirmed) = handle_prompt_keys(&mut rl, &mut edit.symbol) {
+                if confirmed {
+                    let mut dfa = graph.dfa.clone();
+                    status = match dfa.set_transition(&edit.from, &edit.symbol, &edit.to) {
+                        Ok(()) => {
+                            graph.edit(dfa, w, h);
+                            format!("Added {},{} = {}", edit.from, edit.symbol, edit.to)
+                        }
+                        Err(err) => err,
+                    };
+                }
+                prompt = None;
+                rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+            }
+        } else if let Some(text) = input_prompt.as_mut() {
+            if let Some(confirmed) = handle_prompt_keys(&mut rl, text) {
+                execution = confirmed.then(|| Execution::new(text));
+                input_prompt = None;
+                rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+            }
+        } else if let Some(query) = search.as_mut() {
+            if !handle_search_keys(&mut rl, query, &graph, view.traps, &mut camera) {
+                search = None;
+                rl.set_exit_key(Some(KeyboardKey::KEY_ESCAPE));
+            }
+        } else if rl.is_key_pressed(KeyboardKey::KEY_SLASH) {
+            // Escape closes the search instead of the window, and the `/`
+            // that opened it should not end up in the query.
+            search = Some(Search::default());
+            rl.set_exit_key(None);
+            while rl.get_char_pressed().is_some() {}
+        } else {
+            if handle_physics_keys(&rl, &mut graph.params, &mut selected_param) {
+                graph.unsettle();
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_H) {
+                view.show_help = !view.show_help;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_C) {
+                view.color_mode = view.color_mode.next();
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_V) {
+                view.show_missing = !view.show_missing;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_Y) {
+                view.show_cycle = !view.show_cycle;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_F) {
+                view.edge_frequency = !view.edge_frequency;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_B) {
+                view.parallel_edges = !view.parallel_edges;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_G) {
+                view.centering = view.centering.next();
+                graph.unsettle();
+                status = format!("Centering: {:?}", view.centering);
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_U) {
+                view.undirected = !view.undirected;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_T) {
+                view.traps = view.traps.next();
+                if selected.is_some_and(|i| graph.hidden(i, view.traps)) {
+                    selected = None;
+                }
+                status = format!("Trap states: {:?}", view.traps);
+            }
+            // `+` is usually shifted `=`.
+            let faster = rl.is_key_pressed(KeyboardKey::KEY_EQUAL)
+                || rl.is_key_pressed(KeyboardKey::KEY_KP_ADD);
+            let slower = rl.is_key_pressed(KeyboardKey::KEY_MINUS)
+                || rl.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT);
+            if faster || slower {
+                view.steps_per_frame = if faster {
+                    (view.steps_per_frame + 1).min(MAX_STEPS_PER_FRAME)
+                } else {
+                    (view.steps_per_frame - 1).max(1)
+                };
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_Z) {
+                graph.size_by_degree(!graph.sized_by_degree);
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_L) {
+                if graph.targets.is_some() {
+                    graph.targets = None;
+                    graph.unsettle();
+                } else {
+                    graph.level_layout(w, h);
+                }
+            }
+
+            if let (true, Some(nfa)) = (rl.is_key_pressed(KeyboardKey::KEY_D), &nfa) {
+                view.show_nfa = !view.show_nfa;
+                let sized_by_degree = graph.sized_by_degree;
+                graph = if view.show_nfa {
+                    DrawableGraph::from_nfa(nfa, w, h)
+                } else {
+                    DrawableGraph::new(nfa.to_dfa(), w, h)
+                };
+                graph.size_by_degree(sized_by_degree);
+                selected = None;
+            }
+
+            if rl.is_key_pressed(KeyboardKey::KEY_I) {
+                input_prompt = Some(String::new());
+                rl.set_exit_key(None);
+                while rl.get_char_pressed().is_some() {}
+            }
+            // The arrow keys nudge the selected node instead, if there is one.
+            if let (None, Some(execution)) = (selected, execution.as_mut()) {
+                if rl.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+                    execution.advance(&graph.dfa, true);
+                }
+                if rl.is_key_pressed(KeyboardKey::KEY_LEFT) {
+                    execution.advance(&graph.dfa, false);
+                }
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_X) {
+                status = if view.show_nfa {
+                    "SVG export needs the DFA view (D)".to_string()
+                } else {
+                    export_svg(&graph, &path)
+                };
+                println!("{status}");
+            }
+            if machines.len() > 1 {
+                let step = if rl.is_key_pressed(KeyboardKey::KEY_PAGE_DOWN) {
+                    Some(1)
+                } else if rl.is_key_pressed(KeyboardKey::KEY_PAGE_UP) {
+                    Some(machines.len() - 1)
+                } else {
+                    None
+                };
+                if let Some(step) = step {
+                    // Keep any edits for when this machine is shown again.
+                    machines[machine] = graph.dfa.clone();
+                    machine = (machine + step) % machines.len();
+                    let sized_by_degree = graph.sized_by_degree;
+                    graph = DrawableGraph::new(machines[machine].clone(), w, h);
+                    graph.size_by_degree(sized_by_degree);
+                    (selected, execution, merge) = (None, None, None);
+                    rl.set_window_title(&thread, graph.dfa.name.as_deref().unwrap_or(&path));
+                    status = format!("DFA {} of {}", machine + 1, machines.len());
+                }
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_S) {
+                status = if machines.len() > 1 {
+                    machines[machine] = graph.dfa.clone();
+                    save(&machines, &path)
+                } else {
+                    save(std::slice::from_ref(&graph.dfa), &path)
+                };
+                println!("{status}");
+            }
+            // Click a node to select it, then click another (or the same one
+            // for a self-loop) to add a transition between them. The NFA view
+            // is read-only.
+            if !view.show_nfa {
+                if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                    match (selected, hovered_node(&graph, world_mouse, view.traps)) {
+                        (Some(from), Some(to)) => {
+                            prompt = Some(TransitionPrompt {
+                                from: graph.positions[from].label.clone(),
+                                to: graph.positions[to].label.clone(),
+                                symbol: String::new(),
+                            });
+                            selected = None;
+                            rl.set_exit_key(None);
+                            while rl.get_char_pressed().is_some() {}
+                        }
+                        (None, clicked) => {
+                            selected = clicked;
+                            if let Some(index) = clicked {
+                                let label = &graph.positions[index].label;
+                                status = match graph.dfa.path_to(label) {
+                                    Some(word) if word.is_empty() => {
+                                        format!("{label} is the starting state")
+                                    }
+                                    Some(word) => {
+                                        format!("{label} is reached by {}", word.join(" "))
+                                    }
+                                    None => format!("{label} is unreachable"),
+                                };
+                            }
+                        }
+                        (Some(_), None) => selected = None,
+                    }
+                }
+                if let Some(index) = selected {
+                    if nudge_node(&rl, &mut graph.positions[index]) {
+                        graph.unsettle();
+                    }
+                }
+                if rl.is_key_pressed(KeyboardKey::KEY_M) {
+                    merge = Some(Merge::new(&graph));
+                    selected = None;
+                }
+                if rl.is_key_pressed(KeyboardKey::KEY_N) {
+                    let mut dfa = graph.dfa.clone();
+                    let state = dfa.add_state();
+                    graph.edit(dfa, w, h);
+                    if let Some(node) = graph.positions.iter_mut().find(|node| node.label == state)
+                    {
+                        node.position = world_mouse;
+                    }
+                    status = format!("Added state {state}");
+                }
+                if let (true, Some(index)) = (rl.is_key_pressed(KeyboardKey::KEY_DELETE), selected)
+                {
+                    let state = graph.positions[index].label.clone();
+                    let mut dfa = graph.dfa.clone();
+                    status = match dfa.remove_state(&state) {
+                        Ok(()) => {
+                            graph.edit(dfa, w, h);
+                            format!("Removed state {state}")
+                        }
+                        Err(err) => err,
+                    };
+                    selected = None;
+                }
+            }
+        }
+        let matches = match &search {
+            Some(search) => search_matches(&graph, &search.query, view.traps),
+            None => vec![],
+        };
+
+        let hovered = hovered_node(&graph, world_mouse, view.traps);
+        let current = execution.as_ref().and_then(|execution| {
+            let state = *execution.trace(&graph.dfa).get(execution.position)?;
+            graph.positions.iter().position(|node| node.label == state)
+        });
+
         let mut d = rl.begin_drawing(&thread);
 
         d.clear_background(Color::WHITE);
-        d.draw_text("Hello, world!", 12, 12, 20, Color::BLACK);
-
-        update_graph(&mut graph);
-        draw_graph(&mut d, &graph);
+        d.draw_text(
+            graph.dfa.name.as_deref().unwrap_or(&path),
+            12,
+            12,
+            20,
+            Color::BLACK,
+        );
+        draw_physics_hud(&mut d, &graph.params, selected_param);
+        if graph.settled {
+            d.draw_text("settled", 12, 130, 14, Color::DARKGREEN);
+        }
+        if nfa.is_some() {
+            let shown = if view.show_nfa {
+                "NFA"
+            } else {
+                "subset construction"
+            };
+            d.draw_text(
+                &format!("{shown} (D to toggle)"),
+                12,
+                146,
+                14,
+                Color::DARKBLUE,
+            );
+        }
+        d.draw_text(
+            &format!("speed {}x (+/-)", view.steps_per_frame),
+            12,
+            162,
+            14,
+            Color::DARKGRAY,
+        );
 
-        d.draw_circle(w / 2, h / 2, 5.0, Color::YELLOW);
+        for _ in 0..view.steps_per_frame {
+            if layout_steps < TIMED_LAYOUT_STEPS {
+                let start = Instant::now();
+                update_graph(&mut graph, view.centering, w, h);
+                layout_time += start.elapsed();
+                layout_steps += 1;
+                if verbose && layout_steps == TIMED_LAYOUT_STEPS {
+                    eprintln!("first {layout_steps} layout steps: {layout_time:?}");
+                }
+            } else {
+                update_graph(&mut graph, view.centering, w, h);
+            }
+        }
+        if let Some(execution) = &mut execution {
+            execution.fade();
+        }
+        {
+            let mut d = d.begin_mode2D(camera);
+            if let Some(execution) = &execution {
+                draw_trail(&mut d, &graph, execution);
+            }
+            draw_graph(&mut d, &graph, &view, hovered, selected, current, &matches);
+            for center in &graph.centers {
+                d.draw_circle_v(*center, 5.0, Color::YELLOW);
+            }
+        }
+        if let Some(index) = hovered {
+            draw_tooltip(&mut d, &graph, index, mouse);
+        }
+        if let Some(execution) = &execution {
+            draw_execution(&mut d, &graph.dfa, execution, w);
+        }
+        if view.show_help {
+            draw_help(&mut d, w, h);
+        } else {
+            let hint = "H for help";
+            d.draw_text(
+                hint,
+                w - 12 - measure_text(hint, 14),
+                h - 24,
+                14,
+                Color::GRAY,
+            );
+        }
+        if let Some(err) = &drop_error {
+            draw_error(&mut d, err, w, h);
+        }
+        if let Some(search) = &search {
+            let text = format!("/{}  ({} matches)", search.query, matches.len());
+            d.draw_text(&text, 12, h - 24, 16, Color::DARKBLUE);
+        } else if let Some(text) = &input_prompt {
+            let text = format!("input: {text}_  (Enter to run)");
+            d.draw_text(&text, 12, h - 24, 16, Color::DARKBLUE);
+        } else if let Some(edit) = &prompt {
+            let text = format!(
+                "{},{} = {}  (Enter to add)",
+                edit.from, edit.symbol, edit.to
+            );
+            d.draw_text(&text, 12, h - 24, 16, Color::DARKGREEN);
+        } else if !status.is_empty() {
+            d.draw_text(&status, 12, h - 24, 16, Color::DARKGRAY);
+        }
         // break;
 
         drop(d);
     }
 }
 
-fn update_graph(graph: &mut DrawableGraph) {
-    for i in 0..graph.positions.len() {
-        let center = Vector2::new(320.0, 240.0);
-        let x1 = center - graph.positions[i].position;
-        graph.positions[i].acceleration = x1.normalized().scale_by(0.01 * x1.length());
-    }
-    for i in 0..graph.positions.len() {
-        for j in i + 1..graph.positions.len() {
-            let dir = graph.positions[j].position - graph.positions[i].position;
-            if graph.graph.adj_mat.contains_key(&(
-                graph.positions[i].label.clone(),
-                graph.positions[j].label.clone(),
-            )) || graph.graph.adj_mat.contains_key(&(
-                graph.positions[j].label.clone(),
-                graph.positions[i].label.clone(),
-            )) {
-                // a = b / d ^ 3
-                // graph.positions[i].acceleration +=
-                //     dir.normalized().scale_by(dir.length().powi(2) / 10.0);
-                // graph.positions[j].acceleration -=
-                //     dir.normalized().scale_by(dir.length().powi(2) / 10.0);
-                graph.positions[i].acceleration +=
-                    dir.normalized().scale_by(0.1 * (dir.length() / 0.2).ln());
-                graph.positions[j].acceleration -=
-                    dir.normalized().scale_by(0.1 * (dir.length() / 0.2).ln());
-                // graph.positions[i].acceleration +=
-                //     dir.normalized().scale_by(0.1 * (dir.length() - 200.0));
-                // graph.positions[j].acceleration -=
-                //     dir.normalized().scale_by(0.1 * (dir.length() - 200.0));
-                // graph.positions[i].acceleration += dir.normalized().scale_by(0.1 * dir.length());
-                // graph.positions[j].acceleration -= dir.normalized().scale_by(0.1 * dir.length());
-            }
-            let repulsive_force = dir.scale_by(100.0 / dir.length_sqr());
-            graph.positions[i].acceleration -= repulsive_force;
-            graph.positions[j].acceleration += repulsive_force;
-        }
-    }
-    for i in 0..graph.positions.len() {
-        let node = &mut graph.positions[i];
-        node.velocity += node.acceleration.scale_by(0.1);
-        node.velocity.scale(0.97);
-        node.position += node.velocity.scale_by(1.0);
-    }
-}
-
-fn draw_graph(mut d: &mut RaylibDrawHandle, graph: &DrawableGraph) {
+// Keys 1-5 select a physics parameter and `[`/`]` decrease/increase it.
+// Returns whether a parameter changed.
+fn handle_physics_keys(
+    rl: &RaylibHandle,
+    params: &mut PhysicsParams,
+    selected: &mut usize,
+) -> bool {
+    let select_keys = [
+        KeyboardKey::KEY_ONE,
+        KeyboardKey::KEY_TWO,
+        KeyboardKey::KEY_THREE,
+        KeyboardKey::KEY_FOUR,
+        KeyboardKey::KEY_FIVE,
+    ];
+    for (i, key) in select_keys.into_iter().enumerate() {
+        if rl.is_key_pressed(key) {
+            *selected = i;
+        }
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_LEFT_BRACKET) {
+        params.adjust(*selected, false);
+        return true;
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_RIGHT_BRACKET) {
+        params.adjust(*selected, true);
+        return true;
+    }
+    false
+}
+
+// Handles typing while the search is open. Enter focuses the current match
+// and Tab cycles through the matches. Returns false once Escape closes it.
+fn handle_search_keys(
+    rl: &mut RaylibHandle,
+    search: &mut Search,
+    graph: &DrawableGraph,
+    traps: Traps,
+    camera: &mut Camera2D,
+) -> bool {
+    if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+        return false;
+    }
+    while let Some(ch) = rl.get_char_pressed() {
+        search.query.push(ch);
+        search.cursor = 0;
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+        search.query.pop();
+        search.cursor = 0;
+    }
+
+    let matches = search_matches(graph, &search.query, traps);
+    if matches.is_empty() {
+        return true;
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_TAB) {
+        search.cursor = (search.cursor + 1) % matches.len();
+        camera.target = graph.positions[matches[search.cursor]].position;
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+        camera.target = graph.positions[matches[search.cursor % matches.len()]].position;
+    }
+    true
+}
+
+// `minimize <file>` prints the minimal DFA of a `.dfa` file, or of standard
+// input if the file is `-`, without opening a window. Returns the exit code,
+// or `None` if `args` are not a headless command.
+fn headless(args: &[String]) -> Option<i32> {
+    let [command, path] = args else {
+        return None;
+    };
+    if command != "minimize" {
+        return None;
+    }
+    let code = if path == "-" {
+        let mut code = String::new();
+        io::stdin()
+            .read_to_string(&mut code)
+            .map(|_| code)
+            .map_err(|err| format!("Failed to read standard input: {err}"))
+    } else {
+        fs::read_to_string(path).map_err(|err| format!("Failed to read '{path}': {err}"))
+    };
+    let source = if path == "-" { "stdin" } else { path };
+    match code.and_then(|code| {
+        code.parse::<DFA>()
+            .map_err(|err| format!("{source}: {err}"))
+    }) {
+        Ok(dfa) => {
+            println!("{}", String::from(&dfa.minimize()));
+            Some(0)
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            Some(1)
+        }
+    }
+}
+
+// Command line of the window, every part optional and in any order.
+#[derive(Debug, Default)]
+struct Args {
+    // The file to open.
+    path: Option<String>,
+    // `--seed N` for the initial placement.
+    seed: Option<u32>,
+    // `--verbose` logs how long loading and the first layout steps take.
+    verbose: bool,
+}
+
+const USAGE: &str = "usage: Automata [--seed N] [--verbose] [file]
+       Automata minimize <file|->";
+
+// Anything but the known flags and a single file is an error, so a mistyped
+// flag is not opened as a file.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            let value = args.next().ok_or("--seed needs a number")?;
+            parsed.seed = Some(
+                value
+                    .parse()
+                    .map_err(|_| format!("--seed needs a number, got '{value}'"))?,
+            );
+        } else if arg == "--verbose" {
+            parsed.verbose = true;
+        } else if arg.starts_with('-') {
+            return Err(format!("Unknown option '{arg}'"));
+        } else if let Some(path) = &parsed.path {
+            return Err(format!(
+                "Only one file can be opened, got '{path}' and '{arg}'"
+            ));
+        } else {
+            parsed.path = Some(arg);
+        }
+    }
+    Ok(parsed)
+}
+
+// Set from `--verbose` once on startup.
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+// Runs `f`, printing how long it took to stderr under `--verbose`.
+fn timed<T>(what: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("{what}: {:?}", start.elapsed());
+    }
+    result
+}
+
+// Reads a `.dfa` file, or a `.nfa` file which is returned alongside its
+// view. A `.dfa` file can hold several machines separated by `---`; all of
+// them are returned and the first is shown. Errors are messages meant for
+// the window.
+#[allow(clippy::type_complexity)]
+fn load(path: &str, w: i32, h: i32) -> Result<(DrawableGraph, Option<NFA>, Vec<DFA>), String> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    if !matches!(extension, Some("dfa" | "nfa")) {
+        return Err(format!(
+            "Cannot open '{path}', expected a .dfa or .nfa file."
+        ));
+    }
+    let code = fs::read_to_string(path).map_err(|err| format!("Failed to read '{path}': {err}"))?;
+    let in_file = |err: String| format!("{path}: {err}");
+    if extension == Some("nfa") {
+        let nfa: NFA = timed("parse", || code.parse()).map_err(in_file)?;
+        let graph = timed("graph", || DrawableGraph::from_nfa(&nfa, w, h));
+        Ok((graph, Some(nfa), vec![]))
+    } else {
+        let machines = timed("parse", || DFA::parse_many(&code)).map_err(in_file)?;
+        let graph = timed("graph", || DrawableGraph::new(machines[0].clone(), w, h));
+        Ok((graph, None, machines))
+    }
+}
+
+// A box across the middle of the window listing the lines of `err`.
+fn draw_error(d: &mut RaylibDrawHandle, err: &str, w: i32, h: i32) {
+    let lines: Vec<&str> = err.lines().collect();
+    let height = 18 * lines.len() as i32 + 28;
+    let top = (h - height) / 2;
+    d.draw_rectangle(20, top, w - 40, height, Color::RED.fade(0.15));
+    d.draw_rectangle_lines(20, top, w - 40, height, Color::RED);
+    for (i, line) in lines.iter().enumerate() {
+        d.draw_text(line, 30, top + 8 + 18 * i as i32, 14, Color::MAROON);
+    }
+    let hint = "click to dismiss";
+    d.draw_text(
+        hint,
+        w - 30 - measure_text(hint, 10),
+        top + height - 14,
+        10,
+        Color::GRAY,
+    );
+}
+
+// Every key handled in the main loop, in the order the help lists them.
+const KEYS: [(&str, &str); 28] = [
+    ("H", "toggle this help"),
+    ("/", "search states, Tab/Enter to focus"),
+    ("I", "run an input, Left/Right to step"),
+    ("C", "color: distance, redundant, loops, reach"),
+    ("V", "show missing transitions"),
+    ("Y", "highlight a cycle"),
+    ("F", "edge thickness by symbol count"),
+    ("B", "one edge per symbol"),
+    ("U", "undirected edges"),
+    ("T", "trap states: dimmed, hidden"),
+    ("G", "centering: attract, free, confine"),
+    ("Z", "size nodes by degree"),
+    ("L", "leveled layout on/off"),
+    ("D", "NFA / subset construction"),
+    ("1-5", "select a physics parameter"),
+    ("[ ]", "decrease/increase it"),
+    ("+ -", "layout steps per frame"),
+    ("click", "select, then click a target"),
+    ("", "    to add a transition"),
+    ("arrows", "nudge the selected state"),
+    ("P", "pin the selected state"),
+    ("N", "new state at the cursor"),
+    ("Delete", "remove the selected state"),
+    ("M", "animate minimization"),
+    ("PgUp/PgDn", "previous/next DFA in the file"),
+    ("S", "save as <name>.edited.dfa"),
+    ("X", "export <name>.svg"),
+    ("Esc", "close prompt, or quit"),
+];
+
+// What each ring and color drawn on the graph means.
+const LEGEND: [(Color, &str); 9] = [
+    (Color::GOLD, "current state of the run"),
+    (Color::LIME, "selected"),
+    (Color::ORANGE, "hovered, with its edges"),
+    (Color::SKYBLUE, "search match"),
+    (Color::DARKGRAY, "pinned (outer ring)"),
+    (Color::LIGHTGRAY, "dead or unreachable state"),
+    (Color::PURPLE, "cycle (Y)"),
+    (Color::GREEN, "trail of an accepted run"),
+    (Color::RED, "rejected run, missing sink (V)"),
+];
+
+// A translucent panel over the graph with the key bindings on the left and
+// the legend on the right.
+fn draw_help(d: &mut RaylibDrawHandle, w: i32, h: i32) {
+    let (line_height, font_size) = (16, 14);
+    d.draw_rectangle(20, 20, w - 40, h - 40, Color::WHITE.fade(0.9));
+    d.draw_rectangle_lines(20, 20, w - 40, h - 40, Color::DARKGRAY);
+    for (i, (key, action)) in KEYS.iter().enumerate() {
+        let y = 32 + line_height * i as i32;
+        d.draw_text(key, 32, y, font_size, Color::DARKBLUE);
+        d.draw_text(action, 112, y, font_size, Color::BLACK);
+    }
+    let x = w / 2 + 40;
+    for (i, (color, meaning)) in LEGEND.iter().enumerate() {
+        let y = 32 + line_height * i as i32;
+        d.draw_ring(
+            Vector2::new(x as f32, (y + font_size / 2) as f32),
+            4.0,
+            7.0,
+            0.0,
+            360.0,
+            16,
+            *color,
+        );
+        d.draw_text(meaning, x + 14, y, font_size, Color::BLACK);
+    }
+}
+
+// The first file dropped onto the window this frame, if any.
+fn dropped_file(rl: &mut RaylibHandle) -> Option<String> {
+    if !rl.is_file_dropped() {
+        return None;
+    }
+    let files = rl.load_dropped_files();
+    files.paths().first().map(|path| path.to_string())
+}
+
+// Shown instead of the graph when nothing could be loaded on startup. Waits
+// for a file to be dropped that loads, returning it with its path, or `None`
+// if the window is closed first.
+#[allow(clippy::type_complexity)]
+fn wait_for_drop(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    mut error: String,
+    w: i32,
+    h: i32,
+) -> Option<(String, (DrawableGraph, Option<NFA>, Vec<DFA>))> {
+    while !rl.window_should_close() {
+        if let Some(path) = dropped_file(rl) {
+            match load(&path, w, h) {
+                Ok(loaded) => return Some((path, loaded)),
+                Err(err) => error = err,
+            }
+        }
+        let mut d = rl.begin_drawing(thread);
+        d.clear_background(Color::WHITE);
+        let prompt = "Drop a .dfa or .nfa file here,";
+        d.draw_text(
+            prompt,
+            (w - measure_text(prompt, 20)) / 2,
+            h / 2 - 40,
+            20,
+            Color::BLACK,
+        );
+        let prompt = "or pass one on the command line.";
+        d.draw_text(
+            prompt,
+            (w - measure_text(prompt, 20)) / 2,
+            h / 2 - 16,
+            20,
+            Color::BLACK,
+        );
+        for (i, line) in error.lines().enumerate() {
+            d.draw_text(line, 12, h / 2 + 24 + 18 * i as i32, 14, Color::RED);
+        }
+    }
+    None
+}
+
+// Writes `dfas` next to the file they were loaded from as
+// `<name>.edited.dfa`, separated by `---`, leaving the original untouched.
+// Returns a message saying where they went or why it failed.
+fn save(dfas: &[DFA], loaded_from: &str) -> String {
+    let target = Path::new(loaded_from).with_extension("edited.dfa");
+    // Refuse rather than write a file that will not load again.
+    if let Some(err) = dfas
+        .iter()
+        .find_map(|dfa| DFA::try_from(String::from(dfa)).err())
+    {
+        return format!("Not saving {}: {err}", target.display());
+    }
+    let code: Vec<String> = dfas.iter().map(String::from).collect();
+    match fs::write(&target, code.join("\n---\n") + "\n") {
+        Ok(()) => format!("Saved {}", target.display()),
+        Err(err) => format!("Failed to save {}: {err}", target.display()),
+    }
+}
+
+// Arrow keys move `node` by `NUDGE` pixels and `P` pins it in place or
+// releases it. Returns whether anything changed.
+fn nudge_node(rl: &RaylibHandle, node: &mut DisplayNodeElement) -> bool {
+    let arrows = [
+        (KeyboardKey::KEY_LEFT, Vector2::new(-NUDGE, 0.0)),
+        (KeyboardKey::KEY_RIGHT, Vector2::new(NUDGE, 0.0)),
+        (KeyboardKey::KEY_UP, Vector2::new(0.0, -NUDGE)),
+        (KeyboardKey::KEY_DOWN, Vector2::new(0.0, NUDGE)),
+    ];
+    let mut changed = false;
+    for (key, offset) in arrows {
+        if rl.is_key_pressed(key) {
+            node.position += offset;
+            changed = true;
+        }
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_P) {
+        node.pinned = !node.pinned;
+        changed = true;
+    }
+    changed
+}
+
+// Writes the current layout as `<name>.svg` next to the loaded file.
+fn export_svg(graph: &DrawableGraph, loaded_from: &str) -> String {
+    let layout: HashMap<String, Point> = graph
+        .positions
+        .iter()
+        .map(|node| (node.label.clone(), to_point(node.position)))
+        .collect();
+    let target = Path::new(loaded_from).with_extension("svg");
+    match fs::write(&target, graph.dfa.to_svg(&layout)) {
+        Ok(()) => format!("Exported {}", target.display()),
+        Err(err) => format!("Failed to export {}: {err}", target.display()),
+    }
+}
+
+// Handles typing into a one-line prompt. Returns `Some(true)` once Enter
+// confirms it and `Some(false)` if Escape cancels it.
+fn handle_prompt_keys(rl: &mut RaylibHandle, text: &mut String) -> Option<bool> {
+    if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+        return Some(false);
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+        return Some(true);
+    }
+    while let Some(ch) = rl.get_char_pressed() {
+        text.push(ch);
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+        text.pop();
+    }
+    None
+}
+
+fn search_matches(graph: &DrawableGraph, query: &str, traps: Traps) -> Vec<usize> {
+    if query.is_empty() {
+        return vec![];
+    }
+    let query = query.to_lowercase();
+    graph
+        .positions
+        .iter()
+        .enumerate()
+        .filter(|&(i, node)| !graph.hidden(i, traps) && node.label.to_lowercase().contains(&query))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn draw_physics_hud(d: &mut RaylibDrawHandle, params: &PhysicsParams, selected: usize) {
+    for (i, name) in PhysicsParams::NAMES.iter().enumerate() {
+        let color = if i == selected {
+            Color::RED
+        } else {
+            Color::DARKGRAY
+        };
+        let text = format!("{} {name}: {:.3}", i + 1, params.get(i));
+        d.draw_text(&text, 12, 40 + 16 * i as i32, 14, color);
+    }
+}
+
+// Runs one step of the layout. Outside the leveled layout, `centering`
+// decides whether nodes are pulled towards their component's center or, for
+// `Confine`, pushed back into the `w` x `h` window.
+fn update_graph(graph: &mut DrawableGraph, centering: Centering, w: i32, h: i32) {
+    if graph.settled {
+        return;
+    }
+    let strength = match centering {
+        Centering::Attract => graph.params.centering,
+        Centering::Free | Centering::Confine => 0.0,
+    };
+    // In the leveled layout a node is held near its own slot, strongly
+    // enough that the other forces only nudge it.
+    let anchors: Vec<(Point, f32)> = match &graph.targets {
+        Some(targets) => targets
+            .iter()
+            .map(|&target| (to_point(target), 0.2))
+            .collect(),
+        None => graph
+            .positions
+            .iter()
+            .map(|node| (to_point(graph.centers[node.component]), strength))
+            .collect(),
+    };
+    let mut bodies: Vec<Body> = graph
+        .positions
+        .iter()
+        .map(|node| Body {
+            position: to_point(node.position),
+            velocity: to_point(node.velocity),
+            pinned: node.pinned,
+        })
+        .collect();
+    let energy = layout::step(&mut bodies, &graph.adjacent, &anchors, &graph.params);
+    if centering == Centering::Confine && graph.targets.is_none() {
+        for (body, node) in bodies.iter_mut().zip(&graph.positions) {
+            let inside = Point::new(
+                body.position.x.clamp(node.size, w as f32 - node.size),
+                body.position.y.clamp(node.size, h as f32 - node.size),
+            );
+            if !body.pinned {
+                body.velocity += (inside - body.position).scale_by(CONFINE_STRENGTH);
+            }
+        }
+    }
+    for (node, body) in graph.positions.iter_mut().zip(bodies) {
+        node.position = to_vector(body.position);
+        node.velocity = to_vector(body.velocity);
+    }
+    if energy < SETTLE_ENERGY {
+        graph.calm_frames += 1;
+    } else {
+        graph.calm_frames = 0;
+    }
+    if graph.calm_frames >= SETTLE_FRAMES {
+        graph.settled = true;
+        graph.positions.iter_mut().for_each(|node| {
+            node.velocity = Vector2::zero();
+        });
+    }
+}
+
+fn hovered_node(graph: &DrawableGraph, mouse: Vector2, traps: Traps) -> Option<usize> {
+    graph
+        .positions
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|&(i, node)| !graph.hidden(i, traps) && node.position.distance_to(mouse) <= node.size)
+        .map(|(i, _)| i)
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+    Color::new(
+        lerp(from.r, to.r),
+        lerp(from.g, to.g),
+        lerp(from.b, to.b),
+        lerp(from.a, to.a),
+    )
+}
+
+// `reached` holds the states reachable from the selected node, if any, and is
+// only used by `ColorMode::Reach`.
+fn node_color(
+    graph: &DrawableGraph,
+    view: &View,
+    index: usize,
+    reached: Option<&HashSet<String>>,
+) -> Color {
+    match view.color_mode {
+        ColorMode::Plain => graph.positions[index].color,
+        ColorMode::Distance => {
+            let max = graph.distances.iter().flatten().max().copied().unwrap_or(0);
+            match graph.distances[index] {
+                Some(distance) => lerp_color(
+                    Color::GREEN,
+                    Color::DARKBLUE,
+                    distance as f32 / max.max(1) as f32,
+                ),
+                None => Color::LIGHTGRAY,
+            }
+        }
+        ColorMode::Redundant => group_color(&graph.redundant, index),
+        ColorMode::Loops => group_color(&graph.loops, index),
+        ColorMode::Reach => {
+            let is_reached =
+                reached.is_some_and(|reached| reached.contains(&graph.positions[index].label));
+            match (is_reached, graph.live[index]) {
+                (true, true) => lerp_color(REACHED, LIVE, 0.5),
+                (true, false) => REACHED,
+                (false, true) => LIVE,
+                (false, false) => Color::LIGHTGRAY,
+            }
+        }
+    }
+}
+
+// One hue per group in `group_of`, gray for nodes in none.
+fn group_color(group_of: &[Option<usize>], index: usize) -> Color {
+    let groups = group_of.iter().flatten().max().map_or(0, |max| max + 1);
+    match group_of[index] {
+        Some(group) => Color::color_from_hsv(360.0 * group as f32 / groups as f32, 0.7, 0.9),
+        None => Color::LIGHTGRAY,
+    }
+}
+
+fn node_label(graph: &DrawableGraph, view: &View, index: usize) -> String {
+    let label = &graph.positions[index].label;
+    match (view.color_mode, graph.distances[index]) {
+        (ColorMode::Distance, Some(distance)) => format!("{label} ({distance})"),
+        _ => label.clone(),
+    }
+}
+
+fn draw_graph(
+    d: &mut impl RaylibDraw,
+    graph: &DrawableGraph,
+    view: &View,
+    hovered: Option<usize>,
+    selected: Option<usize>,
+    current: Option<usize>,
+    matches: &[usize],
+) {
+    let reached = match (view.color_mode, selected) {
+        (ColorMode::Reach, Some(i)) => {
+            Some(graph.dfa.reachable_from_state(&graph.positions[i].label))
+        }
+        _ => None,
+    };
     graph.positions.iter().enumerate().for_each(|(i, node)| {
-        d.draw_circle_v(node.position, node.size, node.color);
+        let fade = match view.traps {
+            Traps::Hidden if graph.trap[i] => return,
+            Traps::Dimmed if graph.trap[i] => TRAP_FADE,
+            _ => 1.0,
+        };
+        d.draw_circle_v(
+            node.position,
+            node.size,
+            node_color(graph, view, i, reached.as_ref()).fade(fade),
+        );
+        if current == Some(i) {
+            d.draw_ring(
+                node.position,
+                node.size,
+                node.size + 5.0,
+                0.0,
+                360.0,
+                32,
+                Color::GOLD,
+            );
+        } else if selected == Some(i) {
+            d.draw_ring(
+                node.position,
+                node.size,
+                node.size + 3.0,
+                0.0,
+                360.0,
+                32,
+                Color::LIME,
+            );
+        } else if hovered == Some(i) {
+            d.draw_ring(
+                node.position,
+                node.size,
+                node.size + 3.0,
+                0.0,
+                360.0,
+                32,
+                Color::ORANGE,
+            );
+        } else if matches.contains(&i) {
+            d.draw_ring(
+                node.position,
+                node.size,
+                node.size + 3.0,
+                0.0,
+                360.0,
+                32,
+                Color::SKYBLUE,
+            );
+        }
+        if node.pinned {
+            d.draw_ring(
+                node.position,
+                node.size + 6.0,
+                node.size + 8.0,
+                0.0,
+                360.0,
+                32,
+                Color::DARKGRAY,
+            );
+        }
         d.draw_text(
-            &node.label,
+            &node_label(graph, view, i),
             node.position.x as i32,
             node.position.y as i32,
             15,
-            Color::BLACK,
+            Color::BLACK.fade(fade),
         );
     });
 
-    graph.positions.iter().for_each(|start| {
-        graph.positions.iter().for_each(|end| {
-            if graph
-                .graph
-                .adj_mat
-                .contains_key(&(start.label.clone(), end.label.clone()))
-            {
-                draw_edge(&mut d, start, end, 15.0);
+    let mut placed_labels = vec![];
+    for &(i, j, ref symbols) in &graph.edges {
+        // Only a trap's own loops leave it, so hiding the edges into a trap
+        // hides all of its edges.
+        let into_trap = graph.trap[j] && view.traps != Traps::Shown;
+        if into_trap && view.traps == Traps::Hidden {
+            continue;
+        }
+        // The undirected view draws `j -> i` together with `i -> j`.
+        let reverse = graph
+            .edges
+            .binary_search_by(|(from, to, _)| (*from, *to).cmp(&(j, i)))
+            .ok()
+            .filter(|_| view.undirected && i != j);
+        if reverse.is_some() && i > j {
+            continue;
+        }
+        let symbols: Cow<[String]> = match reverse {
+            Some(reverse) => {
+                let mut merged = [symbols.as_slice(), &graph.edges[reverse].2].concat();
+                merged.sort();
+                merged.dedup();
+                Cow::Owned(merged)
             }
-        });
-    });
+            None => Cow::Borrowed(symbols),
+        };
+        let symbols = symbols.as_ref();
+
+        let (start, end) = (&graph.positions[i], &graph.positions[j]);
+        let hovered_end = hovered == Some(i) || (view.undirected && hovered == Some(j));
+        let highlight = if into_trap {
+            Some((1.0, Color::LIGHTGRAY))
+        } else if hovered_end {
+            Some((2.0, Color::ORANGE))
+        } else if view.show_cycle && graph.cycle.contains(&(i, j)) {
+            Some((2.0, Color::PURPLE))
+        } else if view.edge_frequency {
+            Some(frequency_style(graph, symbols))
+        } else {
+            None
+        };
+        if view.undirected {
+            draw_undirected_edge(d, start, end, highlight);
+            draw_edge_label(d, start, end, &graph::label(symbols), &mut placed_labels);
+        } else if view.parallel_edges {
+            draw_parallel_edges(d, start, end, symbols, highlight, &mut placed_labels);
+        } else {
+            draw_edge(d, start, end, 1.0, highlight);
+            draw_edge_label(d, start, end, &graph::label(symbols), &mut placed_labels);
+        }
+    }
+
+    if view.show_missing {
+        draw_missing_sink(d, graph);
+    }
 }
 
-fn draw_edge(
-    d: &mut RaylibDrawHandle,
+// Draws the symbols of an edge near its midpoint, or above the node for a
+// self-loop. If that spot overlaps a label in `placed`, the label is nudged
+// sideways and then along the edge, keeping the first free spot.
+fn draw_edge_label(
+    d: &mut impl RaylibDraw,
     start: &DisplayNodeElement,
     end: &DisplayNodeElement,
-    arrow_size: f32,
+    text: &str,
+    placed: &mut Vec<Rectangle>,
 ) {
-    // Start and end points of the line
+    let font_size = 12;
+    let (base, dir) = if start.label == end.label {
+        let above = Vector2::new(0.0, -start.size - font_size as f32);
+        (start.position + above, Vector2::new(1.0, 0.0))
+    } else {
+        let dir = end.position - start.position;
+        (start.position.lerp(end.position, 0.5), dir.scale_by(0.5))
+    };
+    let perp = Vector2::new(dir.y, -dir.x).normalized();
+
+    let width = measure_text(text, font_size) as f32;
+    let rect_at = |center: Vector2| {
+        Rectangle::new(
+            center.x - width / 2.0,
+            center.y - font_size as f32 / 2.0,
+            width,
+            font_size as f32,
+        )
+    };
+    let candidates = [0.0, 1.0, -1.0, 2.0, -2.0, 3.0, -3.0]
+        .into_iter()
+        .map(|k| base + perp.scale_by(k * font_size as f32))
+        .chain([0.3, -0.3].into_iter().map(|k| base + dir.scale_by(k)));
+    let rect = candidates
+        .map(rect_at)
+        .find(|rect| !placed.iter().any(|other| rect.check_collision_recs(other)))
+        .unwrap_or_else(|| rect_at(base));
 
+    d.draw_text(
+        text,
+        rect.x as i32,
+        rect.y as i32,
+        font_size,
+        Color::DARKGRAY,
+    );
+    placed.push(rect);
+}
+
+// Draws a stand-in sink to the right of the graph with a red edge from every
+// node that lacks a transition, labelled with the missing symbols.
+fn draw_missing_sink(d: &mut impl RaylibDraw, graph: &DrawableGraph) {
+    if graph.missing.iter().all(Vec::is_empty) {
+        return;
+    }
+    let right = graph
+        .positions
+        .iter()
+        .map(|node| node.position.x + node.size)
+        .fold(f32::MIN, f32::max);
+    let mean_y = graph
+        .positions
+        .iter()
+        .map(|node| node.position.y)
+        .sum::<f32>()
+        / graph.positions.len() as f32;
+    let (sink, radius) = (Vector2::new(right + 100.0, mean_y), 20.0);
+
+    d.draw_circle_lines(sink.x as i32, sink.y as i32, radius, Color::RED);
+    d.draw_text(
+        "missing",
+        sink.x as i32 - 20,
+        (sink.y + radius) as i32 + 4,
+        12,
+        Color::RED,
+    );
+    for (node, symbols) in graph.positions.iter().zip(&graph.missing) {
+        if symbols.is_empty() {
+            continue;
+        }
+        let mid = node.position.lerp(sink, 0.5);
+        d.draw_line_ex(node.position, sink, 1.0, Color::RED.fade(0.5));
+        d.draw_text(
+            &graph::label(symbols),
+            mid.x as i32,
+            mid.y as i32,
+            12,
+            Color::RED,
+        );
+    }
+}
+
+// Thick lines along the edges an execution recently took, fading as their
+// frames run out, green if the whole input is accepted and red otherwise. A
+// self-loop is drawn as a ring around its node.
+fn draw_trail(d: &mut impl RaylibDraw, graph: &DrawableGraph, execution: &Execution) {
+    let color = if execution.accepted(&graph.dfa) {
+        Color::GREEN
+    } else {
+        Color::RED
+    };
+    let trace = execution.trace(&graph.dfa);
+    let node = |state: &str| graph.positions.iter().find(|node| node.label == state);
+    for (step, &frames) in execution.trail.iter().enumerate() {
+        if frames == 0 || step + 1 >= trace.len() {
+            continue;
+        }
+        let (Some(from), Some(to)) = (node(trace[step]), node(trace[step + 1])) else {
+            continue;
+        };
+        let color = color.fade(frames as f32 / TRAIL_FRAMES as f32);
+        if from.label == to.label {
+            d.draw_ring(
+                from.position,
+                from.size + 8.0,
+                from.size + 12.0,
+                0.0,
+                360.0,
+                32,
+                color,
+            );
+        } else {
+            d.draw_line_ex(from.position, to.position, 6.0, color);
+        }
+    }
+}
+
+// Shows the input along the top with a cursor after the consumed symbols,
+// and the verdict once the whole word is read or the DFA gets stuck.
+fn draw_execution(d: &mut RaylibDrawHandle, dfa: &DFA, execution: &Execution, w: i32) {
+    let trace = execution.trace(dfa);
+    let (done, rest) = execution.input.split_at(execution.position);
+    let text = format!("{} | {}", done.join(" "), rest.join(" "));
+    let x = w - measure_text(&text, 20) - 12;
+    d.draw_text(&text, x, 12, 20, Color::DARKBLUE);
+
+    let verdict = if execution.position >= trace.len() {
+        Some(("rejected: no transition", Color::RED))
+    } else if rest.is_empty() {
+        Some(if dfa.is_accepting(trace[execution.position]) {
+            ("accepted", Color::DARKGREEN)
+        } else {
+            ("rejected", Color::RED)
+        })
+    } else {
+        None
+    };
+    if let Some((verdict, color)) = verdict {
+        d.draw_text(verdict, w - measure_text(verdict, 16) - 12, 36, 16, color);
+    }
+}
+
+// Line thickness and color of an edge labelled with `symbols`, from thin and
+// cool for a single symbol to thick and warm for the whole alphabet.
+fn frequency_style(graph: &DrawableGraph, symbols: &[String]) -> (f32, Color) {
+    let share = symbols.len() as f32 / graph.dfa.alphabet.len().max(1) as f32;
+    (
+        1.0 + 3.0 * share,
+        lerp_color(Color::SKYBLUE, Color::RED, share.min(1.0)),
+    )
+}
+
+// Lists the outgoing edges of a node next to the cursor, with the number of
+// symbols on each, shifted so the box stays inside the window.
+fn draw_tooltip(d: &mut RaylibDrawHandle, graph: &DrawableGraph, index: usize, mouse: Vector2) {
+    let label = &graph.positions[index].label;
+    let mut lines: Vec<String> = graph
+        .edges
+        .iter()
+        .filter(|(start, _, _)| *start == index)
+        .map(|(_, end, symbols)| {
+            let end = &graph.positions[*end].label;
+            format!("{} -> {end} ({})", symbols.join(", "), symbols.len())
+        })
+        .collect();
+    lines.sort();
+    lines.insert(0, label.clone());
+
+    let (font_size, line_height, padding) = (14, 16, 4);
+    let width = lines
+        .iter()
+        .map(|line| measure_text(line, font_size))
+        .max()
+        .unwrap_or(0)
+        + 2 * padding;
+    let height = lines.len() as i32 * line_height + 2 * padding;
+    let x = (mouse.x as i32 + 12)
+        .min(d.get_screen_width() - width)
+        .max(0);
+    let y = (mouse.y as i32 + 12)
+        .min(d.get_screen_height() - height)
+        .max(0);
+
+    d.draw_rectangle(x, y, width, height, Color::LIGHTGRAY.fade(0.9));
+    d.draw_rectangle_lines(x, y, width, height, Color::DARKGRAY);
+    for (i, line) in lines.iter().enumerate() {
+        d.draw_text(
+            line,
+            x + padding,
+            y + padding + i as i32 * line_height,
+            font_size,
+            Color::BLACK,
+        );
+    }
+}
+
+// One edge per symbol, each shifted sideways by `PARALLEL_GAP` more than the
+// last. The first is already off the center line, so the edges of `a -> b`
+// and `b -> a` end up on opposite sides. Self-loop labels stack upwards.
+fn draw_parallel_edges(
+    d: &mut impl RaylibDraw,
+    start: &DisplayNodeElement,
+    end: &DisplayNodeElement,
+    symbols: &[String],
+    highlight: Option<(f32, Color)>,
+    placed: &mut Vec<Rectangle>,
+) {
+    let shift = if start.label == end.label {
+        Vector2::new(0.0, -PARALLEL_GAP)
+    } else {
+        let dir = (end.position - start.position).normalized();
+        Vector2::new(dir.y, -dir.x).scale_by(PARALLEL_GAP)
+    };
+    for (k, symbol) in symbols.iter().enumerate() {
+        let offset = shift.scale_by(k as f32 + 0.5);
+        let (mut start, mut end) = (start.clone(), end.clone());
+        start.position += offset;
+        end.position += offset;
+        draw_edge(d, &start, &end, 0.7, highlight);
+        draw_edge_label(d, &start, &end, symbol, placed);
+    }
+}
+
+// A plain line between the borders of the two nodes, without an arrowhead.
+fn draw_undirected_edge(
+    d: &mut impl RaylibDraw,
+    start: &DisplayNodeElement,
+    end: &DisplayNodeElement,
+    highlight: Option<(f32, Color)>,
+) {
+    let dir = (end.position - start.position).normalized();
+    let (thickness, color) = highlight.unwrap_or((1.0, Color::BLACK));
+    d.draw_line_ex(
+        start.position + dir * start.size,
+        end.position - dir * end.size,
+        thickness,
+        color,
+    );
+}
+
+// A line between the borders of the two nodes with an arrowhead whose tip
+// touches `end`. The arrowhead is `arrow_scale` times `ARROW_FRACTION` of the
+// target's size, shrunk to fit short edges but never below `MIN_ARROW`.
+fn draw_edge(
+    d: &mut impl RaylibDraw,
+    start: &DisplayNodeElement,
+    end: &DisplayNodeElement,
+    arrow_scale: f32,
+    highlight: Option<(f32, Color)>,
+) {
     let mut dir = end.position - start.position;
+    let gap = dir.length() - start.size - end.size;
     dir.normalize();
     let perp = Vector2 {
         x: dir.y,
@@ -167,9 +2037,17 @@ fn draw_edge(
 
     let s = start.position + dir * start.size;
     let e = end.position - dir * end.size;
+    let arrow_size = (end.size * ARROW_FRACTION * arrow_scale)
+        .min(gap)
+        .max(MIN_ARROW);
     let t2 = e - dir.scale_by(arrow_size * 0.86 as f32) + perp.scale_by(0.5 * arrow_size);
     let t3 = e - dir.scale_by(arrow_size * 0.86 as f32) - perp.scale_by(0.5 * arrow_size);
 
-    d.draw_line_ex(s, e, 1.0, Color::BLACK);
-    d.draw_triangle(e, t2, t3, Color::BLUE);
+    if let Some((thickness, color)) = highlight {
+        d.draw_line_ex(s, e, thickness, color);
+        d.draw_triangle(e, t2, t3, color);
+    } else {
+        d.draw_line_ex(s, e, 1.0, Color::BLACK);
+        d.draw_triangle(e, t2, t3, Color::BLUE);
+    }
 }
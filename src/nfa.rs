@@ -0,0 +1,379 @@
+use crate::dfa::{
+    char, keyword, list, marked_state, peek_section, sections, skip_rule, symbols, StateNames, DFA,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    iter::Peekable,
+    str::{CharIndices, FromStr},
+};
+
+// Symbols written in a `.nfa` file for an ε-transition.
+const EPSILON: [&str; 2] = ["ε", "eps"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NFA {
+    pub states: Vec<String>,                                        // Q
+    pub alphabet: Vec<String>,                                      // E
+    pub transition: HashMap<(String, Option<String>), Vec<String>>, // Q * (E + e) -> P(Q)
+    pub starting_state: String,                                     // q0
+    pub accepting_states: Vec<String>,                              // F
+}
+
+impl TryFrom<String> for NFA {
+    type Error = String;
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        NFA::try_from(code.as_str())
+    }
+}
+
+impl FromStr for NFA {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        NFA::try_from(code)
+    }
+}
+
+// Same format as a `.dfa` file, except that a transition may lead to a list
+// of states, `q0,a = [q0, q1];`, and `ε` (or `eps`) marks an ε-transition.
+impl TryFrom<&str> for NFA {
+    type Error = String;
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        let mut char_indices = code.char_indices().peekable();
+        let parsed = sections(&mut char_indices, transitions)?;
+        let nfa = NFA {
+            states: parsed.states,
+            alphabet: parsed.alphabet,
+            transition: parsed.transitions,
+            starting_state: parsed.starting_state,
+            accepting_states: parsed.accepting_states,
+        };
+
+        if !nfa.states.contains(&nfa.starting_state) {
+            return Err(format!("{} is not a valid State.", nfa.starting_state));
+        }
+        if let Some(state) = nfa
+            .accepting_states
+            .iter()
+            .find(|state| !nfa.states.contains(state))
+        {
+            return Err(format!("Accepting State {state} is not a valid state."));
+        }
+        for ((start, symbol), ends) in &nfa.transition {
+            let symbol_valid = symbol
+                .as_ref()
+                .is_none_or(|symbol| nfa.alphabet.contains(symbol));
+            let states_valid = std::iter::once(start)
+                .chain(ends)
+                .all(|state| nfa.states.contains(state));
+            if !symbol_valid || !states_valid {
+                let symbol = symbol.as_deref().unwrap_or(EPSILON[0]);
+                return Err(format!(
+                    "Transition {start},{symbol} -> [{}] is invalid",
+                    ends.join(", ")
+                ));
+            }
+        }
+        Ok(nfa)
+    }
+}
+
+impl From<&DFA> for NFA {
+    fn from(dfa: &DFA) -> NFA {
+        NFA {
+            states: dfa.states.clone(),
+            alphabet: dfa.alphabet.clone(),
+            transition: dfa
+                .transition
+                .iter()
+                .map(|((start, alphabet), end)| {
+                    ((start.clone(), Some(alphabet.clone())), vec![end.clone()])
+                })
+                .collect(),
+            starting_state: dfa.starting_state.clone(),
+            accepting_states: dfa.accepting_states.clone(),
+        }
+    }
+}
+
+impl NFA {
+    /// Renames every state `q` to `prefix_q`, so that two machines can be
+    /// combined without their state names clashing.
+    pub fn prefixed(self, prefix: &str) -> NFA {
+        let rename = |state: &String| format!("{prefix}_{state}");
+        NFA {
+            states: self.states.iter().map(rename).collect(),
+            alphabet: self.alphabet,
+            transition: self
+                .transition
+                .iter()
+                .map(|((start, alphabet), ends)| {
+                    (
+                        (rename(start), alphabet.clone()),
+                        ends.iter().map(rename).collect(),
+                    )
+                })
+                .collect(),
+            starting_state: rename(&self.starting_state),
+            accepting_states: self.accepting_states.iter().map(rename).collect(),
+        }
+    }
+
+    /// All states reachable from `states` using only ε-transitions.
+    pub fn epsilon_closure(&self, states: impl IntoIterator<Item = String>) -> BTreeSet<String> {
+        let mut closure = BTreeSet::new();
+        let mut stack: Vec<String> = states.into_iter().collect();
+        while let Some(state) = stack.pop() {
+            if closure.insert(state.clone()) {
+                if let Some(ends) = self.transition.get(&(state, None)) {
+                    stack.extend(ends.iter().cloned());
+                }
+            }
+        }
+        closure
+    }
+
+    fn step(&self, states: &BTreeSet<String>, symbol: &str) -> BTreeSet<String> {
+        let targets = states.iter().flat_map(|state| {
+            self.transition
+                .get(&(state.clone(), Some(symbol.to_string())))
+                .into_iter()
+                .flatten()
+                .cloned()
+        });
+        self.epsilon_closure(targets.collect::<Vec<_>>())
+    }
+
+    pub fn accepts(&self, word: &[&str]) -> bool {
+        let mut current = self.epsilon_closure([self.starting_state.clone()]);
+        for symbol in word {
+            current = self.step(&current, symbol);
+        }
+        current
+            .iter()
+            .any(|state| self.accepting_states.contains(state))
+    }
+
+    /// Subset construction. Each DFA state is named after the set of NFA
+    /// states it stands for joined with `_`, e.g. `q1_q2`, so the result can
+    /// be saved and parsed back. A set whose name is already taken, as `{a_b}`
+    /// after `{a, b}`, gets a number appended, e.g. `a_b_2`. Only reachable,
+    /// non-empty sets are created, so the result is partial where the NFA gets
+    /// stuck.
+    pub fn to_dfa(&self) -> DFA {
+        let mut names = StateNames::new();
+        let mut name = |set: &BTreeSet<String>| {
+            names.get(set, || set.iter().cloned().collect::<Vec<_>>().join("_"))
+        };
+
+        let accepting: HashSet<&String> = self.accepting_states.iter().collect();
+        let start = self.epsilon_closure([self.starting_state.clone()]);
+        let mut seen = HashSet::from([start.clone()]);
+        let mut queue = VecDeque::from([start.clone()]);
+        let mut dfa = DFA {
+            name: None,
+            states: vec![],
+            alphabet: self.alphabet.clone(),
+            transition: BTreeMap::new(),
+            starting_state: name(&start),
+            accepting_states: vec![],
+            dead_state: None,
+            accepting: Default::default(),
+        };
+
+        while let Some(set) = queue.pop_front() {
+            dfa.states.push(name(&set));
+            if set.iter().any(|state| accepting.contains(state)) {
+                dfa.accepting_states.push(name(&set));
+            }
+            for symbol in &self.alphabet {
+                let next = self.step(&set, symbol);
+                if next.is_empty() {
+                    continue;
+                }
+                dfa.transition
+                    .insert((name(&set), symbol.clone()), name(&next));
+                if seen.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        dfa
+    }
+}
+
+// Like the DFA transitions, but each rule adds to the targets of its state
+// and symbol instead of replacing them.
+#[allow(clippy::type_complexity)]
+fn transitions(
+    code: &mut Peekable<CharIndices>,
+    errors: &mut Vec<String>,
+) -> Result<(HashMap<(String, Option<String>), Vec<String>>, Vec<String>), String> {
+    keyword(code, "transitions")?;
+    char(code, '=')?;
+
+    let mut transitions = HashMap::<(String, Option<String>), Vec<String>>::new();
+    let mut accepting = vec![];
+    while code.peek().is_some() && peek_section(code).is_none() {
+        let (start_state, inputs, final_states) = match transition(code, &mut accepting) {
+            Ok(rule) => rule,
+            Err(err) => {
+                errors.push(err);
+                skip_rule(code);
+                continue;
+            }
+        };
+        for input in inputs {
+            let symbol = (!EPSILON.contains(&input.as_str())).then_some(input);
+            let ends = transitions
+                .entry((start_state.clone(), symbol))
+                .or_default();
+            for state in &final_states {
+                if !ends.contains(state) {
+                    ends.push(state.clone());
+                }
+            }
+        }
+    }
+    Ok((transitions, accepting))
+}
+
+// A single rule `start,symbols = end;` or `start,symbols = [ends];`.
+fn transition(
+    code: &mut Peekable<CharIndices>,
+    accepting: &mut Vec<String>,
+) -> Result<(String, Vec<String>, Vec<String>), String> {
+    let start_state = marked_state(code, accepting);
+    if start_state.is_empty() {
+        return match code.peek() {
+            Some((_, x)) => Err(format!("Unexpected Symbol '{x}' Expected a state")),
+            None => Err("Unexpected End of File.".to_string()),
+        };
+    }
+    char(code, ',')?;
+    let inputs = symbols(code)?;
+    char(code, '=')?;
+
+    let final_states = match code.peek() {
+        Some((_, '[')) => list(code)?,
+        _ => vec![marked_state(code, accepting)],
+    };
+    char(code, ';')?;
+    Ok((start_state, inputs, final_states))
+}
+
+#[cfg(test)]
+mod nfa_tests {
+    use std::fs;
+
+    use super::*;
+
+    // Accepts words over {a, b} whose second to last symbol is `a`.
+    fn second_last_a() -> NFA {
+        NFA {
+            states: vec!["q0".to_string(), "q1".to_string(), "q2".to_string()],
+            alphabet: vec!["a".to_string(), "b".to_string()],
+            transition: HashMap::from([
+                (
+                    ("q0".to_string(), Some("a".to_string())),
+                    vec!["q0".to_string(), "q1".to_string()],
+                ),
+                (
+                    ("q0".to_string(), Some("b".to_string())),
+                    vec!["q0".to_string()],
+                ),
+                (
+                    ("q1".to_string(), Some("a".to_string())),
+                    vec!["q2".to_string()],
+                ),
+                (
+                    ("q1".to_string(), Some("b".to_string())),
+                    vec!["q2".to_string()],
+                ),
+            ]),
+            starting_state: "q0".to_string(),
+            accepting_states: vec!["q2".to_string()],
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let nfa = NFA::try_from(fs::read_to_string("./test.nfa").unwrap()).unwrap();
+        assert_eq!(
+            nfa.transition[&("q0".to_string(), Some("a".to_string()))],
+            vec!["q0", "q1"]
+        );
+        assert_eq!(nfa.transition[&("q2".to_string(), None)], vec!["q3"]);
+        assert!(nfa.accepts(&["b", "a", "b"]));
+        assert!(!nfa.accepts(&["a", "b", "b"]));
+    }
+
+    #[test]
+    fn parse_invalid_target() {
+        let code = "states = [q0]
+alphabet = [a]
+starting_state = q0
+accepting_states = []
+transitions =
+    q0,a = [q0, q1];";
+        assert_eq!(
+            code.parse::<NFA>(),
+            Err("Transition q0,a -> [q0, q1] is invalid".to_string())
+        );
+    }
+
+    #[test]
+    fn epsilon_closure() {
+        let mut nfa = second_last_a();
+        nfa.transition
+            .insert(("q1".to_string(), None), vec!["q2".to_string()]);
+        assert_eq!(
+            nfa.epsilon_closure(["q1".to_string()]),
+            BTreeSet::from(["q1".to_string(), "q2".to_string()])
+        );
+    }
+
+    #[test]
+    fn to_dfa_preserves_language() {
+        let nfa = second_last_a();
+        let dfa = nfa.to_dfa();
+        for word in [
+            vec![],
+            vec!["a"],
+            vec!["a", "b"],
+            vec!["b", "a"],
+            vec!["b", "a", "a"],
+            vec!["a", "b", "b"],
+        ] {
+            assert_eq!(dfa.accepts(&word), nfa.accepts(&word), "{word:?}");
+        }
+        assert_eq!(dfa.states.len(), 4);
+    }
+
+    #[test]
+    fn to_dfa_names_parse_back() {
+        let dfa = second_last_a().to_dfa();
+        assert_eq!(dfa.starting_state, "q0");
+        assert!(dfa.states.contains(&"q0_q1_q2".to_string()));
+        assert_eq!(DFA::try_from(String::from(&dfa)), Ok(dfa));
+    }
+
+    #[test]
+    fn to_dfa_names_do_not_collide() {
+        // `{a, b}` and `{a_b}` would both be named `a_b`.
+        let nfa = NFA::try_from(
+            "states = [s, a, b, a_b]
+alphabet = [x, y]
+starting_state = s
+accepting_states = [a]
+transitions =
+    s,x = [a, b];
+    s,y = a_b;",
+        )
+        .unwrap();
+        let dfa = nfa.to_dfa();
+        assert_eq!(dfa.states, vec!["s", "a_b", "a_b_2"]);
+        assert!(dfa.accepts(&["x"]));
+        assert!(!dfa.accepts(&["y"]));
+        assert_eq!(DFA::try_from(String::from(&dfa)), Ok(dfa));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::nfa::NFA;
+use std::{collections::HashMap, iter::Peekable, str::CharIndices};
+
+/// Whether `input`, read one character per symbol, matches the whole of
+/// `pattern`. The pattern goes through the full pipeline: Thompson's
+/// construction to an NFA, the subset construction to a DFA, then a run.
+pub fn regex_matches(pattern: &str, input: &str) -> Result<bool, String> {
+    NFA::from_regex(pattern)?.to_dfa().accepts_str(input)
+}
+
+impl NFA {
+    /// Thompson's construction. A pattern is made of single characters
+    /// combined with `|`, `*`, `+`, `?` and parentheses; `\` takes the next
+    /// character literally. The alphabet is the characters used, in order of
+    /// appearance, and states are named `r0`, `r1`, ...
+    pub fn from_regex(pattern: &str) -> Result<NFA, String> {
+        let mut builder = Builder::default();
+        let mut code = pattern.char_indices().peekable();
+        let (start, end) = builder.alternation(&mut code)?;
+        if let Some((_, x)) = code.next() {
+            return Err(format!("Unexpected Symbol '{x}' Expected a symbol or '('"));
+        }
+        Ok(NFA {
+            states: (0..builder.states).map(|i| format!("r{i}")).collect(),
+            alphabet: builder.alphabet,
+            transition: builder.transition,
+            starting_state: start,
+            accepting_states: vec![end],
+        })
+    }
+}
+
+// The NFA under construction. Each sub-pattern becomes a fragment with one
+// start and one end state, joined to the others by ε-transitions.
+#[derive(Default)]
+struct Builder {
+    states: usize,
+    alphabet: Vec<String>,
+    transition: HashMap<(String, Option<String>), Vec<String>>,
+}
+
+impl Builder {
+    fn state(&mut self) -> String {
+        self.states += 1;
+        format!("r{}", self.states - 1)
+    }
+
+    fn edge(&mut self, from: &str, symbol: Option<String>, to: &str) {
+        self.transition
+            .entry((from.to_string(), symbol))
+            .or_default()
+            .push(to.to_string());
+    }
+
+    // `concatenation ('|' concatenation)*`
+    fn alternation(
+        &mut self,
+        code: &mut Peekable<CharIndices>,
+    ) -> Result<(String, String), String> {
+        let first = self.concatenation(code)?;
+        if code.peek().is_none_or(|(_, ch)| *ch != '|') {
+            return Ok(first);
+        }
+        let (start, end) = (self.state(), self.state());
+        let mut branch = first;
+        loop {
+            self.edge(&start, None, &branch.0);
+            self.edge(&branch.1, None, &end);
+            if code.next_if(|(_, ch)| *ch == '|').is_none() {
+                return Ok((start, end));
+            }
+            branch = self.concatenation(code)?;
+        }
+    }
+
+    // Repetitions one after another, possibly none for the empty word.
+    fn concatenation(
+        &mut self,
+        code: &mut Peekable<CharIndices>,
+    ) -> Result<(String, String), String> {
+        let mut fragment: Option<(String, String)> = None;
+        while code.peek().is_some_and(|(_, ch)| *ch != '|' && *ch != ')') {
+            let next = self.repetition(code)?;
+            fragment = Some(match fragment {
+                Some((start, end)) => {
+                    self.edge(&end, None, &next.0);
+                    (start, next.1)
+                }
+                None => next,
+            });
+        }
+        Ok(fragment.unwrap_or_else(|| {
+            let (start, end) = (self.state(), self.state());
+            self.edge(&start, None, &end);
+            (start, end)
+        }))
+    }
+
+    // An atom followed by any number of `*`, `+` and `?`.
+    fn repetition(&mut self, code: &mut Peekable<CharIndices>) -> Result<(String, String), String> {
+        let mut inner = self.atom(code)?;
+        while let Some((_, op)) = code.next_if(|(_, ch)| matches!(ch, '*' | '+' | '?')) {
+            let (start, end) = (self.state(), self.state());
+            self.edge(&start, None, &inner.0);
+            self.edge(&inner.1, None, &end);
+            if op != '+' {
+                self.edge(&start, None, &end);
+            }
+            if op != '?' {
+                self.edge(&inner.1, None, &inner.0);
+            }
+            inner = (start, end);
+        }
+        Ok(inner)
+    }
+
+    // A character, an escaped character or a parenthesized pattern.
+    fn atom(&mut self, code: &mut Peekable<CharIndices>) -> Result<(String, String), String> {
+        let symbol = match code.next() {
+            Some((_, '(')) => {
+                let inner = self.alternation(code)?;
+                return match code.next() {
+                    Some((_, ')')) => Ok(inner),
+                    Some((_, x)) => Err(format!("Unexpected Symbol '{x}' Expected ')'")),
+                    None => Err("Unexpected End of Pattern. Expected ')'".to_string()),
+                };
+            }
+            Some((_, '\\')) => match code.next() {
+                Some((_, ch)) => ch,
+                None => return Err("Unexpected End of Pattern.".to_string()),
+            },
+            Some((_, x @ ('*' | '+' | '?' | ')' | '|'))) => {
+                return Err(format!("Unexpected Symbol '{x}' Expected a symbol or '('"))
+            }
+            Some((_, ch)) => ch,
+            None => return Err("Unexpected End of Pattern.".to_string()),
+        };
+        let symbol = symbol.to_string();
+        if !self.alphabet.contains(&symbol) {
+            self.alphabet.push(symbol.clone());
+        }
+        let (start, end) = (self.state(), self.state());
+        self.edge(&start, Some(symbol), &end);
+        Ok((start, end))
+    }
+}
+
+#[cfg(test)]
+mod regex_tests {
+    use super::*;
+
+    #[test]
+    fn ends_with_abb() {
+        for input in ["abb", "aabb", "babb", "abababb"] {
+            assert_eq!(regex_matches("(a|b)*abb", input), Ok(true), "{input}");
+        }
+        for input in ["", "ab", "abba", "abbc", "bbb"] {
+            assert_eq!(regex_matches("(a|b)*abb", input), Ok(false), "{input}");
+        }
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(regex_matches("ab+c?", "abbb"), Ok(true));
+        assert_eq!(regex_matches("ab+c?", "abc"), Ok(true));
+        assert_eq!(regex_matches("ab+c?", "ac"), Ok(false));
+        assert_eq!(regex_matches("a(|b)", "a"), Ok(true));
+        assert_eq!(regex_matches("a(|b)", "ab"), Ok(true));
+        assert_eq!(regex_matches("", ""), Ok(true));
+        assert_eq!(regex_matches(r"a\*", "a*"), Ok(true));
+        assert_eq!(regex_matches(r"a\*", "aa"), Ok(false));
+    }
+
+    #[test]
+    fn alphabet_in_order() {
+        let nfa = NFA::from_regex("(b|a)*ab").unwrap();
+        assert_eq!(nfa.alphabet, vec!["b", "a"]);
+        assert!(nfa.accepts(&["b", "a", "b"]));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            NFA::from_regex("(ab"),
+            Err("Unexpected End of Pattern. Expected ')'".to_string())
+        );
+        assert_eq!(
+            NFA::from_regex("a)"),
+            Err("Unexpected Symbol ')' Expected a symbol or '('".to_string())
+        );
+        assert_eq!(
+            NFA::from_regex("*a"),
+            Err("Unexpected Symbol '*' Expected a symbol or '('".to_string())
+        );
+        assert_eq!(
+            NFA::from_regex("a\\"),
+            Err("Unexpected End of Pattern.".to_string())
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{dfa::DFA, layout::Point};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+const RADIUS: f32 = 20.0;
+const MARGIN: f32 = 40.0;
+
+impl DFA {
+    /// Renders the DFA as an SVG document, placing each state at its position
+    /// in `layout`, such as the one `layout::force_layout` returns. Accepting
+    /// states get a double circle and the starting state an incoming arrow.
+    /// States missing from `layout` are left out, along with their
+    /// transitions.
+    pub fn to_svg(&self, layout: &HashMap<String, Point>) -> String {
+        let position: HashMap<&str, (f32, f32)> = layout
+            .iter()
+            .filter(|(state, _)| self.states.contains(state))
+            .map(|(state, point)| (state.as_str(), (point.x, point.y)))
+            .collect();
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+        for (i, (x, y)) in position.values().enumerate() {
+            if i == 0 {
+                (min_x, min_y, max_x, max_y) = (*x, *y, *x, *y);
+            }
+            (min_x, min_y) = (min_x.min(*x), min_y.min(*y));
+            (max_x, max_y) = (max_x.max(*x), max_y.max(*y));
+        }
+        // Room for the node itself, self-loops above it and the start arrow.
+        let (left, top) = (min_x - RADIUS - MARGIN * 2.0, min_y - RADIUS - MARGIN * 2.0);
+        let (width, height) = (
+            max_x - left + RADIUS + MARGIN,
+            max_y - top + RADIUS + MARGIN,
+        );
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{left} {top} {width} {height}" font-family="sans-serif" font-size="14">"#
+        )
+        .unwrap();
+        svg.push_str(
+            r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8" markerHeight="8" orient="auto-start-reverse"><path d="M 0 0 L 10 5 L 0 10 z"/></marker></defs>
+"#,
+        );
+
+        let mut edges: BTreeMap<(&str, &str), Vec<&str>> = BTreeMap::new();
+        for (start, symbol, end) in self.transitions_iter() {
+            edges.entry((start, end)).or_default().push(symbol);
+        }
+        for ((start, end), symbols) in edges {
+            let (Some(&from), Some(&to)) = (position.get(start), position.get(end)) else {
+                continue;
+            };
+            edge(&mut svg, from, to, &escape(&symbols.join(", ")));
+        }
+
+        if let Some(&(x, y)) = position.get(self.starting_state.as_str()) {
+            writeln!(
+                svg,
+                r#"<line x1="{}" y1="{y}" x2="{}" y2="{y}" stroke="black" marker-end="url(#arrow)"/>"#,
+                x - RADIUS - MARGIN,
+                x - RADIUS
+            )
+            .unwrap();
+        }
+        for state in &self.states {
+            let Some(&(x, y)) = position.get(state.as_str()) else {
+                continue;
+            };
+            writeln!(
+                svg,
+                r#"<circle cx="{x}" cy="{y}" r="{RADIUS}" fill="white" stroke="black"/>"#
+            )
+            .unwrap();
+            if self.is_accepting(state) {
+                writeln!(
+                    svg,
+                    r#"<circle cx="{x}" cy="{y}" r="{}" fill="none" stroke="black"/>"#,
+                    RADIUS - 4.0
+                )
+                .unwrap();
+            }
+            writeln!(
+                svg,
+                r#"<text x="{x}" y="{y}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                escape(state)
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+// A labelled arrow between the rims of two nodes, or a loop above the node
+// when both ends are the same.
+fn edge(svg: &mut String, (x1, y1): (f32, f32), (x2, y2): (f32, f32), label: &str) {
+    if (x1, y1) == (x2, y2) {
+        writeln!(
+            svg,
+            r#"<path d="M {} {} C {} {} {} {} {} {}" fill="none" stroke="black" marker-end="url(#arrow)"/>"#,
+            x1 - 8.0,
+            y1 - RADIUS + 2.0,
+            x1 - 25.0,
+            y1 - RADIUS - 40.0,
+            x1 + 25.0,
+            y1 - RADIUS - 40.0,
+            x1 + 8.0,
+            y1 - RADIUS + 2.0
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{x1}" y="{}" text-anchor="middle">{label}</text>"#,
+            y1 - RADIUS - 36.0
+        )
+        .unwrap();
+        return;
+    }
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let length = (dx * dx + dy * dy).sqrt();
+    let (ux, uy) = (dx / length, dy / length);
+    writeln!(
+        svg,
+        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black" marker-end="url(#arrow)"/>"#,
+        x1 + ux * RADIUS,
+        y1 + uy * RADIUS,
+        x2 - ux * RADIUS,
+        y2 - uy * RADIUS
+    )
+    .unwrap();
+    // Labels sit beside the line, on the left of its direction, so the two
+    // edges between a pair of nodes label on opposite sides.
+    writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle">{label}</text>"#,
+        (x1 + x2) / 2.0 + uy * 12.0,
+        (y1 + y2) / 2.0 - ux * 12.0
+    )
+    .unwrap();
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod svg_tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        graph::Graph,
+        layout::{force_layout, PhysicsParams},
+    };
+
+    fn layout(dfa: &DFA) -> HashMap<String, Point> {
+        dfa.states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| {
+                let point = Point::new(100.0 * i as f32, 50.0 * (i % 2) as f32);
+                (state.clone(), point)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn double_circles_for_accepting() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        let svg = dfa.to_svg(&layout(&dfa));
+        assert_eq!(svg.matches("<circle").count(), 4 + 2);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn edges_and_labels() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let svg = dfa.to_svg(&layout(&dfa));
+        assert_eq!(svg.matches("<circle").count(), 4);
+        // One straight edge plus the start arrow, and two self-loops besides
+        // the arrowhead marker.
+        assert_eq!(svg.matches("<line").count(), 2);
+        assert_eq!(svg.matches("<path").count(), 2 + 1);
+        assert!(svg.contains(">a, b</text>"));
+    }
+
+    #[test]
+    fn one_edge_per_pair_of_states() {
+        // `q0` goes to `q1` on `a` and `c`, with `b` to `q0` in between.
+        let dfa = DFA::try_from(
+            "states = [q0, q1]
+alphabet = [a, b, c]
+starting_state = q0
+accepting_states = [q1]
+transitions =
+    q0,a = q1;
+    q0,b = q0;
+    q0,c = q1;",
+        )
+        .unwrap();
+        let svg = dfa.to_svg(&layout(&dfa));
+        assert!(svg.contains(">a, c</text>"), "{svg}");
+        assert_eq!(svg.matches("<text").count(), 2 + 2);
+    }
+
+    #[test]
+    fn from_force_layout() {
+        let dfa = DFA::try_from(fs::read_to_string("./three.dfa").unwrap()).unwrap();
+        let layout = force_layout(&Graph::from(dfa.clone()), 50, &PhysicsParams::default());
+        assert_eq!(dfa.to_svg(&layout).matches("<circle").count(), 4 + 2);
+    }
+
+    #[test]
+    fn unplaced_states_are_skipped() {
+        let dfa = DFA::try_from(fs::read_to_string("./test.dfa").unwrap()).unwrap();
+        let mut layout = layout(&dfa);
+        layout.remove("q2");
+        let svg = dfa.to_svg(&layout);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(!svg.contains(">q2</text>"));
+    }
+}
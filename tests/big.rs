@@ -0,0 +1,67 @@
+use automata::{dfa::DFA, graph::Graph};
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+// Generous enough for a debug build on a slow machine; only a parser that
+// has gone pathological should come near it.
+const BUDGET: Duration = Duration::from_secs(5);
+
+// Parses `code` and converts it to a `Graph` as the window does on load,
+// failing if either errors or the two together go over `BUDGET`.
+fn load(code: &str) -> (DFA, Graph) {
+    let start = Instant::now();
+    let dfa = DFA::try_from(code).unwrap();
+    let graph = Graph::from(dfa.clone());
+    let elapsed = start.elapsed();
+    assert!(elapsed < BUDGET, "took {elapsed:?}");
+    (dfa, graph)
+}
+
+#[test]
+fn big_dfa() {
+    let Ok(code) = fs::read_to_string("./big.dfa") else {
+        eprintln!("big.dfa not found, skipping");
+        return;
+    };
+    let (dfa, graph) = load(&code);
+    assert_eq!(graph.nodes, dfa.states);
+}
+
+#[test]
+fn generated_dfa() {
+    let dfa = DFA::random(2000, &["a", "b", "c"], 11);
+    let (parsed, graph) = load(&dfa.to_string());
+    assert_eq!(parsed, dfa);
+    assert_eq!(graph.nodes.len(), 2000);
+}
+
+// Times the string-keyed `accepts` against `IndexedDFA::accepts` on the same
+// words. Ignored since it only reports; run it with
+// `cargo test --release --test big -- --ignored --nocapture`. A release run
+// took about 170ms string-keyed against 5ms indexed for these 10 000 words.
+#[test]
+#[ignore]
+fn indexed_against_string_keyed() {
+    let symbols = ["a", "b", "c"];
+    let dfa = DFA::random(1000, &symbols, 5);
+    let indexed = dfa.to_indexed();
+    let words: Vec<Vec<&str>> = (0..10_000usize)
+        .map(|i| (0..100).map(|j| symbols[(i * 31 + j * j) % 3]).collect())
+        .collect();
+    let encoded: Vec<Vec<usize>> = words
+        .iter()
+        .map(|word| indexed.encode(word).unwrap())
+        .collect();
+
+    let start = Instant::now();
+    let by_string: Vec<bool> = words.iter().map(|word| dfa.accepts(word)).collect();
+    let string_keyed = start.elapsed();
+    let start = Instant::now();
+    let by_index: Vec<bool> = encoded.iter().map(|word| indexed.accepts(word)).collect();
+    let interned = start.elapsed();
+
+    assert_eq!(by_string, by_index);
+    println!("string-keyed {string_keyed:?}, indexed {interned:?}");
+}
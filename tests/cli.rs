@@ -0,0 +1,66 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+// Runs the binary with `args`, feeding `input` on standard input.
+fn run(args: &[&str], input: &str) -> (bool, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_Automata"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn minimize_from_stdin() {
+    // Both states of test.dfa accept everything, so they collapse into one.
+    let (ok, stdout, _) = run(
+        &["minimize", "-"],
+        &std::fs::read_to_string("./test.dfa").unwrap(),
+    );
+    assert!(ok);
+    assert_eq!(
+        stdout,
+        "states = [q1]
+alphabet = [a, b]
+starting_state = q1
+accepting_states = [q1]
+transitions =
+    q1,a = q1;
+    q1,b = q1;
+"
+    );
+}
+
+#[test]
+fn minimize_reports_parse_errors() {
+    let (ok, stdout, stderr) = run(&["minimize", "-"], "states = [q1");
+    assert!(!ok);
+    assert!(stdout.is_empty());
+    assert!(stderr.starts_with("stdin: "), "{stderr}");
+}
+
+#[test]
+fn rejects_unknown_flags() {
+    let (ok, _, stderr) = run(&["--sed", "3", "test.dfa"], "");
+    assert!(!ok);
+    assert!(
+        stderr.starts_with("Unknown option '--sed'\nusage: "),
+        "{stderr}"
+    );
+}
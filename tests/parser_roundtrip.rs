@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use automata::dfa::DFA;
+use proptest::prelude::*;
+
+// Random valid DFAs, possibly partial, kept small so the suite stays fast.
+fn dfa_strategy() -> impl Strategy<Value = DFA> {
+    (
+        prop::collection::hash_set("[a-z][a-z0-9_]{0,4}", 1..6),
+        prop::collection::hash_set("[a-z0-9]{1,2}", 1..4),
+        prop::option::of("[A-Za-z0-9 ]{0,12}"),
+    )
+        .prop_flat_map(|(states, alphabet, name)| {
+            let states: Vec<String> = states.into_iter().collect();
+            let alphabet: Vec<String> = alphabet.into_iter().collect();
+            let n = states.len();
+            let transitions = n * alphabet.len();
+            (
+                Just(name),
+                Just(states),
+                Just(alphabet),
+                0..n,
+                prop::collection::vec(any::<bool>(), n),
+                prop::collection::vec(prop::option::of(0..n), transitions),
+            )
+        })
+        .prop_map(|(name, states, alphabet, start, accepting, targets)| {
+            let mut transition = BTreeMap::new();
+            for (i, target) in targets.into_iter().enumerate() {
+                if let Some(target) = target {
+                    let key = (
+                        states[i / alphabet.len()].clone(),
+                        alphabet[i % alphabet.len()].clone(),
+                    );
+                    transition.insert(key, states[target].clone());
+                }
+            }
+            DFA {
+                name,
+                accepting_states: states
+                    .iter()
+                    .zip(accepting)
+                    .filter(|(_, accepting)| *accepting)
+                    .map(|(state, _)| state.clone())
+                    .collect(),
+                starting_state: states[start].clone(),
+                states,
+                alphabet,
+                transition,
+                dead_state: None,
+                accepting: Default::default(),
+            }
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn serialize_then_parse(dfa in dfa_strategy()) {
+        let code: String = (&dfa).into();
+        prop_assert_eq!(DFA::try_from(code), Ok(dfa));
+    }
+}